@@ -6,6 +6,7 @@ use crate::{
         Test,
         Gear,
     },
+    types::AccountId,
     runtime
 };
 
@@ -20,7 +21,7 @@ pub struct GasEstimation {
 
 pub struct CalculateGasCall {
     contract_address: ActorId,
-    signer: Option<u64>, 
+    signer: Option<AccountId>, 
     service_name: Option<String>, 
     method_name: Option<String>, 
     value: u128, 
@@ -53,7 +54,7 @@ impl CalculateGasCall {
         self
     }
 
-    pub fn signer(mut self, signer: u64) -> Self {
+    pub fn signer(mut self, signer: AccountId) -> Self {
         self.signer = Some(signer);
 
         self
@@ -122,8 +123,6 @@ impl CalculateGasCall {
     pub fn calculate_gas(self) -> GasEstimation { // u128
         self.check_data();
 
-        runtime::start_transaction();
-
         let payload = if !self.no_sails_command {
             [
                 self.service_name.unwrap().encode(),
@@ -152,7 +151,7 @@ impl CalculateGasCall {
         //     user_payload
         // };
 
-        let res = Gear::calculate_gas_info(
+        let res = runtime::with_rollback(|| Gear::calculate_gas_info(
             self.signer.unwrap().into_origin(), 
             pallet_gear::manager::HandleKind::Handle(self.contract_address), 
             payload, 
@@ -160,7 +159,7 @@ impl CalculateGasCall {
             self.allow_other_panics, 
             self.initial_gas, 
             self.gas_allowance
-        );
+        ));
 
 
         let info = res.map_err(|e| String::from_utf8(e).unwrap_or_else(|_| "calculate_gas_info failed".into()));
@@ -177,8 +176,6 @@ impl CalculateGasCall {
             // panic!("{message_error}");
         }
 
-        runtime::rollback_transaction();
-
         let info = info.unwrap();
 
         GasEstimation {
@@ -13,6 +13,7 @@ use crate::{
         System,
     },
     types::{
+        AccountId,
         ContractCommandError,
         ContractResponse,
         DEFAULT_GAS_LIMIT
@@ -26,7 +27,7 @@ use crate::{
 
 pub struct CommandCall {
     contract_address: ActorId,
-    signer: Option<u64>,
+    signer: Option<AccountId>,
     service_name: Option<String>,
     method_name: Option<String>,
     gas_limit: Option<u64>,
@@ -79,7 +80,7 @@ impl CommandCall {
         self
     }
 
-    pub fn signer(mut self, signer: u64) -> Self {
+    pub fn signer(mut self, signer: AccountId) -> Self {
         self.signer = Some(signer);
 
         self
@@ -200,16 +201,29 @@ impl CommandCall {
     ) -> Result<ContractResponse<R>, ContractCommandError> {
         self.check_data();
 
-        let is_query = self.is_query;
+        // Queries are rolled back on every return path (and on panic)
+        let result = if self.is_query {
+            runtime::with_rollback(|| self.wait_response::<R>())
+        } else {
+            self.wait_response::<R>()
+        };
+
+        if let Ok(ContractResponse::Response(_)) = &result {
+            runtime::run_to_next_block();
+        }
 
-        if is_query { runtime::start_transaction(); }
+        result
+    }
 
+    fn wait_response<R: Decode>(
+        self
+    ) -> Result<ContractResponse<R>, ContractCommandError> {
         runtime::reset_system_events();
 
         let signer = self.signer
             .clone()
             .unwrap();
-        let signer_origin = ActorId::from_origin(signer.into_origin());
+        let signer_origin = ActorId::from_origin(signer.clone().into_origin());
         let contract_address = self.contract_address
             .clone();
         let mut max_blocks_to_wait = self.max_blocks_to_wait;
@@ -232,10 +246,6 @@ impl CommandCall {
                                     &mut &message.payload_bytes()[..]
                                 ).map_err(|e| ContractCommandError::ResultDecodeError(e.to_string()))?;
 
-                                if is_query { runtime::rollback_transaction(); }
-                                
-                                runtime::run_to_next_block();
-
                                 return Ok(ContractResponse::Response(response.2));
                             }
                         },
@@ -265,8 +275,6 @@ impl CommandCall {
             max_blocks_to_wait -= 1;
         }
 
-        if is_query { runtime::rollback_transaction(); }
-
         Ok(ContractResponse::OkNoReply)
     }
 
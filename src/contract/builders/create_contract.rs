@@ -8,13 +8,13 @@ use crate::{
         Gear,
         RuntimeOrigin
     },
-    types::DEFAULT_GAS_LIMIT,
+    types::{AccountId, DEFAULT_GAS_LIMIT},
     contract::Contract,
     runtime
 };
 
 pub struct CreateContract<T: Encode> {
-    signer: Option<u64>,
+    signer: Option<AccountId>,
     code_id: Option<CodeId>,
     constructor_name: Option<String>,
     init_payload: Option<T>,
@@ -48,7 +48,7 @@ impl<T: Encode> CreateContract<T> {
         self
     } 
 
-    pub fn signer(mut self, signer: u64) -> Self {
+    pub fn signer(mut self, signer: AccountId) -> Self {
         self.signer = Some(signer);
 
         self
@@ -133,9 +133,9 @@ impl<T: Encode> CreateContract<T> {
     }
 }
 
-fn gen_contract_ids(code_id: CodeId, salt: &[u8]) -> (ActorId, u64) {
+fn gen_contract_ids(code_id: CodeId, salt: &[u8]) -> (ActorId, AccountId) {
     let contract_id = ActorId::generate_from_user(code_id, salt);
-    let contract_account_id = u64::from_origin(contract_id.into_origin());
+    let contract_account_id = AccountId::from_origin(contract_id.into_origin());
 
     (contract_id, contract_account_id)
 }
\ No newline at end of file
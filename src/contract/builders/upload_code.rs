@@ -1,21 +1,16 @@
 use frame_support::assert_ok;
 use gear_core::ids::CodeId;
-use crate::{
-    mock::{
-        Gear,
-        RuntimeOrigin
-    },
-};
+use crate::{runtime, types::AccountId};
 use sp_core::blake2_256;
 
 #[derive(Default)]
 pub struct UploadCode {
-    signer: Option<u64>,
+    signer: Option<AccountId>,
     wasm: Option<Vec<u8>>
 }
 
 impl UploadCode {
-    pub fn signer(mut self, signer: u64) -> Self {
+    pub fn signer(mut self, signer: AccountId) -> Self {
         self.signer = Some(signer);
 
         self
@@ -39,8 +34,8 @@ impl UploadCode {
         let wasm = self.wasm.unwrap();
 
         assert_ok!(
-            Gear::upload_code(
-                RuntimeOrigin::signed(self.signer.unwrap()), 
+            runtime::upload_code(
+                self.signer.unwrap(), 
                 wasm.clone()
             )
         );
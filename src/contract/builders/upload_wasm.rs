@@ -4,17 +4,13 @@ use common::Origin;
 use gprimitives::ActorId;
 use gear_core::ids::{prelude::{ActorIdExt, CodeIdExt}, CodeId};
 use crate::{
-    mock::{
-        Gear,
-        RuntimeOrigin
-    },
-    types::DEFAULT_GAS_LIMIT,
+    types::{AccountId, DEFAULT_GAS_LIMIT},
     contract::Contract,
     runtime
 };
 
 pub trait UploadWasmT: Sized {
-    fn signer(self, signer: u64) -> Self;
+    fn signer(self, signer: AccountId) -> Self;
     fn wasm(self, wasm: &[u8]) -> Self;
     fn salt(self, salt: &'static str) -> Self;
     fn gas_limit(self, gas_limit: u64) -> Self;
@@ -23,7 +19,7 @@ pub trait UploadWasmT: Sized {
 }
 
 pub struct UploadSailsWasm<T: Encode> {
-    signer: Option<u64>,
+    signer: Option<AccountId>,
     wasm: Option<Vec<u8>>,
     constructor_name: Option<String>,
     init_payload: Option<T>,
@@ -59,7 +55,7 @@ impl<T: Encode> UploadSailsWasm<T> {
 }
 
 impl<T: Encode> UploadWasmT for UploadSailsWasm<T> {
-    fn signer(mut self, signer: u64) -> Self {
+    fn signer(mut self, signer: AccountId) -> Self {
         self.signer = Some(signer);
 
         self
@@ -126,8 +122,8 @@ impl<T: Encode> UploadWasmT for UploadSailsWasm<T> {
         };
 
         assert_ok!(
-            Gear::upload_program(
-                RuntimeOrigin::signed(self.signer.unwrap()), 
+            runtime::upload_program(
+                self.signer.unwrap(), 
                 self.wasm.unwrap(),//wasm.to_vec(), 
                 self.salt.unwrap(), // salt.to_vec(), 
                 payload, 
@@ -146,7 +142,7 @@ impl<T: Encode> UploadWasmT for UploadSailsWasm<T> {
 
 #[derive(Default)]
 pub struct UploadWasm {
-    signer: Option<u64>,
+    signer: Option<AccountId>,
     wasm: Option<Vec<u8>>,
     salt: Option<Vec<u8>>,
     keep_alive: bool,
@@ -154,7 +150,7 @@ pub struct UploadWasm {
 }
 
 impl UploadWasmT for UploadWasm {
-    fn signer(mut self, signer: u64) -> Self {
+    fn signer(mut self, signer: AccountId) -> Self {
         self.signer = Some(signer);
 
         self
@@ -205,8 +201,8 @@ impl UploadWasmT for UploadWasm {
         let gas_limit = self.gas_limit.unwrap_or(DEFAULT_GAS_LIMIT);
 
         assert_ok!(
-            Gear::upload_program(
-                RuntimeOrigin::signed(self.signer.unwrap()), 
+            runtime::upload_program(
+                self.signer.unwrap(), 
                 self.wasm.unwrap(),
                 self.salt.unwrap(), 
                 Default::default(), 
@@ -222,9 +218,9 @@ impl UploadWasmT for UploadWasm {
     }
 }
 
-fn gen_contract_ids(wasm: &[u8], salt: &[u8]) -> (ActorId, u64) {
+fn gen_contract_ids(wasm: &[u8], salt: &[u8]) -> (ActorId, AccountId) {
     let contract_id = ActorId::generate_from_user(CodeId::generate(wasm), salt);
-    let contract_account_id = u64::from_origin(contract_id.into_origin());
+    let contract_account_id = AccountId::from_origin(contract_id.into_origin());
 
     (contract_id, contract_account_id)
 }
\ No newline at end of file
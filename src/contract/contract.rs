@@ -1,5 +1,7 @@
 use pallet_staking::{RewardDestination, StakingLedger};
 use gprimitives::ActorId;
+use common::Origin;
+use gear_core::ids::{prelude::{ActorIdExt, CodeIdExt}, CodeId};
 use pallet_balances::AccountData;
 use sp_staking::StakingAccount;
 use parity_scale_codec::Encode;
@@ -7,6 +9,7 @@ use crate::mock::{
     System, 
     Test
 };
+use crate::types::AccountId;
 use super::builders::{
     CommandCall,
     // QueryCall,
@@ -17,16 +20,17 @@ use super::builders::{
     CreateContract
 };
 
+#[derive(Clone)]
 pub struct Contract {
     /// ## Contract address
     pub(crate) address: ActorId,
     /// ## System account
     /// Is the account associted with the account (tokens, deposits, staking, etc.)
-    pub(crate) account: u64
+    pub(crate) account: AccountId
 }
 
 impl Contract {
-    pub fn new(address: ActorId, account: u64) -> Self {
+    pub fn new(address: ActorId, account: AccountId) -> Self {
         Self {
             address,
             account
@@ -37,6 +41,26 @@ impl Contract {
         self.address
     }
 
+    /// ## Contract system account
+    pub fn account(&self) -> AccountId {
+        self.account.clone()
+    }
+
+    /// ## Predict a contract
+    /// Returns the contract that will be uploaded with the wasm and the salt, so its account can be
+    /// funded before the upload (e.g. `ExtBuilder::with_balance(contract.account(), balance)`).
+    pub fn predict(wasm: &[u8], salt: &str) -> Self {
+        Self::predict_from_code(CodeId::generate(wasm), salt)
+    }
+
+    /// ## Predict a contract from its code id
+    /// Returns the contract that will be created with the code id and the salt.
+    pub fn predict_from_code(code_id: CodeId, salt: &str) -> Self {
+        let address = ActorId::generate_from_user(code_id, salt.as_bytes());
+
+        Self::new(address, AccountId::from_origin(address.into_origin()))
+    }
+
     pub fn create_contract<R: Encode>() -> CreateContract<R> {
         CreateContract::new()
     }
@@ -70,7 +94,7 @@ impl Contract {
     /// of tokens staked by the contract, etc.
     pub fn stash_ledger(&self) -> StakingLedger<Test> {
         let ledger = pallet_staking::Pallet::<Test>::ledger(
-            StakingAccount::Stash(self.account)
+            StakingAccount::Stash(self.account())
         ).unwrap();
 
         ledger
@@ -78,9 +102,9 @@ impl Contract {
 
     /// ## Contract's payee ledger
     /// Get the ledger associated with the contract payee account.
-    pub fn payee_ledger(&self) -> Option<RewardDestination<u64>> {
+    pub fn payee_ledger(&self) -> Option<RewardDestination<AccountId>> {
         let payee = pallet_staking::Pallet::<Test>::payee(
-            StakingAccount::Stash(self.account)
+            StakingAccount::Stash(self.account())
         );
 
         payee
@@ -88,8 +112,8 @@ impl Contract {
 
 
     /// ## Return contract nominations
-    pub fn nominators(&self) -> Vec<u64> {
-        let targets_before = pallet_staking::Nominators::<Test>::get(self.account)
+    pub fn nominators(&self) -> Vec<AccountId> {
+        let targets_before = pallet_staking::Nominators::<Test>::get(&self.account)
                 .map_or_else(Vec::new, |x| x.targets.into_inner());
 
         targets_before
@@ -98,21 +122,21 @@ impl Contract {
     /// ## Contract free balance
     /// Returns the free tokens of the smart contract. If staked, the value will be: contract_balance - staked_value
     pub fn free_balance(&self) -> u128 {
-        let account_data = System::account(self.account);
+        let account_data = System::account(&self.account);
         account_data.data.free
     }
 
     /// ## Contract frozen balance
     /// Frozen balance that is locked or staked
     pub fn frozen_balance(&self) -> u128 {
-        let account_data = System::account(self.account);
+        let account_data = System::account(&self.account);
         account_data.data.frozen
     }
 
     /// ## Contract account data
     /// Returns more balance data from a contract
     pub fn get_account_data(&self) -> AccountData<u128> {
-        let account_data: AccountData<u128> = System::account(self.account).data;
+        let account_data: AccountData<u128> = System::account(&self.account).data;
 
         account_data
     }
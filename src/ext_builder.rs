@@ -1,5 +1,10 @@
 
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock, PoisonError};
+
 use frame_system::{self as system};
+use parity_scale_codec::Encode;
+use sp_core::storage::Storage;
 use sp_runtime::{
     Perbill,
     BuildStorage,
@@ -7,14 +12,35 @@ use sp_runtime::{
 };
 use crate::types::{
     AccountId,
-    Balance
+    Balance,
+    BONDING_DURATION,
+    DEFAULT_BLOCK_GAS_LIMIT,
+    ERA_PAYOUT,
+    EXISTENTIAL_DEPOSIT,
+    MILLISECS_PER_BLOCK,
+    SESSIONS_PER_ERA,
+    SESSION_DURATION_IN_BLOCKS,
+    VALUE_PER_GAS,
+    VaraRewardsConfig
 };
 use crate::mock::{
     Test,
-    System
+    System,
+    BlockGasLimit,
+    BlockTime,
+    BondingDuration,
+    EraPayoutValue,
+    ExistentialDeposit,
+    GasMultiplier,
+    Offset,
+    Period,
+    SessionsPerEra,
+    SlashDeferDuration,
+    VaraRewards
 };
 
-use crate::runtime;
+use crate::runtime::{self, ChainState, StateSnapshot};
+use crate::staking_helper::rewards_pool_account;
 
 #[derive(Default)]
 pub struct ExtBuilder {
@@ -22,7 +48,44 @@ pub struct ExtBuilder {
     pub initial_authorities: Vec<(AccountId, Option<UintAuthorityId>)>,
     pub endowed_accounts: Vec<AccountId>,
     pub endowment: Balance,
+    // Balances of single accounts, they replace the endowment (zero balance accounts are not created)
+    pub balances: Vec<(AccountId, Balance)>,
     pub enable_sessions: bool,  
+    // Block duration in ms, 3_000 ms if not set
+    pub block_time: Option<u64>,
+    // Block gas limit, 100_000_000_000_000 if not set
+    pub block_gas_limit: Option<u64>,
+    // Sessions in each era, 6 if not set
+    pub sessions_per_era: Option<u32>,
+    // Eras that the funds keep bonded, 14 if not set
+    pub bonding_duration: Option<u32>,
+    // Session duration in blocks, 2_400 if not set
+    pub session_duration: Option<u64>,
+    // Existential deposit, 1 token if not set
+    pub existential_deposit: Option<Balance>,
+    // Value of each unit of gas, 100 if not set
+    pub value_per_gas: Option<Balance>,
+    // Rewards paid to validators on each era, 100 tokens if not set
+    pub era_payout: Option<Balance>,
+    // Vara staking rewards model instead of the fixed era payout
+    pub vara_rewards: Option<VaraRewardsConfig>,
+    // Build the genesis from scratch instead of use the genesis cache
+    pub genesis_cache_disabled: bool,
+    // Reuse the instrumented codes uploaded in other tests (runtime::set_code_cache)
+    pub code_cache: bool,
+    // Check the gear bank and gas tree after each block (runtime::set_gear_invariant_checks)
+    pub gear_invariant_checks: bool,
+    // Session validators author the blocks in turns (runtime::set_author_rotation)
+    pub author_rotation: bool,
+    // Pay the stakers of each era when it ends (runtime::set_auto_payout)
+    pub auto_payout: bool,
+    // Run the try_state hooks after each block (runtime::set_try_state_checks)
+    #[cfg(feature = "try-runtime")]
+    pub try_state_checks: bool,
+    // Raw chain state (exported from a node) loaded over the genesis
+    pub chain_state: Option<ChainState>,
+    // State loaded over the genesis (e.g. from a file saved with StateSnapshot::save)
+    pub state: Option<StateSnapshot>,
 }
 
 impl ExtBuilder {
@@ -36,6 +99,36 @@ impl ExtBuilder {
         self
     }
 
+    /// Adds accounts to the endowed accounts (e.g. named accounts: `[&alice, &bob]`)
+    pub fn with_funded_accounts<A: Into<AccountId>>(
+        mut self,
+        accounts: impl IntoIterator<Item = A>,
+    ) -> Self {
+        self.endowed_accounts
+            .extend(accounts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the balance of an account instead of the endowment, with zero balance the account
+    /// is not created (the balance must be zero or at least the existential deposit)
+    pub fn with_balance(mut self, account: impl Into<AccountId>, balance: Balance) -> Self {
+        self.balances.push((account.into(), balance));
+        self
+    }
+
+    /// Sets the balances of the accounts (`with_balance` for each account)
+    pub fn with_balances<A: Into<AccountId>>(
+        mut self,
+        balances: impl IntoIterator<Item = (A, Balance)>,
+    ) -> Self {
+        self.balances.extend(
+            balances
+                .into_iter()
+                .map(|(account, balance)| (account.into(), balance)),
+        );
+        self
+    }
+
     pub fn with_initial_authorities(
         mut self,
         auths: Vec<(AccountId, Option<UintAuthorityId>)>,
@@ -49,31 +142,198 @@ impl ExtBuilder {
         self
     }
 
+    pub fn with_block_time(mut self, block_time_ms: u64) -> Self {
+        self.block_time = Some(block_time_ms);
+        self
+    }
+
+    pub fn with_block_gas_limit(mut self, gas: u64) -> Self {
+        self.block_gas_limit = Some(gas);
+        self
+    }
+
+    pub fn with_sessions_per_era(mut self, sessions: u32) -> Self {
+        self.sessions_per_era = Some(sessions);
+        self
+    }
+
+    pub fn with_bonding_duration(mut self, eras: u32) -> Self {
+        self.bonding_duration = Some(eras);
+        self
+    }
+
+    pub fn with_session_duration(mut self, blocks: u64) -> Self {
+        self.session_duration = Some(blocks);
+        self
+    }
+
+    pub fn with_existential_deposit(mut self, existential_deposit: Balance) -> Self {
+        self.existential_deposit = Some(existential_deposit);
+        self
+    }
+
+    pub fn with_value_per_gas(mut self, value_per_gas: Balance) -> Self {
+        self.value_per_gas = Some(value_per_gas);
+        self
+    }
+
+    pub fn with_era_payout(mut self, payout: Balance) -> Self {
+        self.era_payout = Some(payout);
+        self
+    }
+
+    /// Uses the Vara staking rewards model for the era payouts (the era payout is not used),
+    /// the rewards pool is funded with `pool_balance` in the genesis
+    pub fn with_vara_rewards(mut self, config: VaraRewardsConfig) -> Self {
+        self.vara_rewards = Some(config);
+        self
+    }
+
+    pub fn without_genesis_cache(mut self) -> Self {
+        self.genesis_cache_disabled = true;
+        self
+    }
+
+    pub fn with_code_cache(mut self) -> Self {
+        self.code_cache = true;
+        self
+    }
+
+    pub fn with_gear_invariant_checks(mut self) -> Self {
+        self.gear_invariant_checks = true;
+        self
+    }
+
+    pub fn with_author_rotation(mut self) -> Self {
+        self.author_rotation = true;
+        self
+    }
+
+    pub fn with_auto_payout(mut self) -> Self {
+        self.auto_payout = true;
+        self
+    }
+
+    #[cfg(feature = "try-runtime")]
+    pub fn with_try_state_checks(mut self) -> Self {
+        self.try_state_checks = true;
+        self
+    }
+
+    pub fn with_chain_state(mut self, chain_state: ChainState) -> Self {
+        self.chain_state = Some(chain_state);
+        self
+    }
+
+    /// Loads the storage of the snapshot over the genesis. The runtime settings saved in the
+    /// snapshot (block time, eras, etc) are dropped and the settings of the builder are used,
+    /// use `StateSnapshot::execute_with` to keep them.
+    pub fn with_state(mut self, state: StateSnapshot) -> Self {
+        self.state = Some(state);
+        self
+    }
+
     pub fn build(self) -> sp_io::TestExternalities {
-        let mut storage = system::GenesisConfig::<Test>::default()
-            .build_storage()
-            .expect("system genesis builds");
+        // --- Runtime settings (kept by thread) ---
 
-        // --- Balances ---
+        BlockTime::set(self.block_time.unwrap_or(MILLISECS_PER_BLOCK));
+        BlockGasLimit::set(self.block_gas_limit.unwrap_or(DEFAULT_BLOCK_GAS_LIMIT));
 
-        let endowed_accounts_iter = self
-            .endowed_accounts
-            .iter()
-            .map(|k| (*k, self.endowment));
+        let bonding_duration = self.bonding_duration.unwrap_or(BONDING_DURATION);
+        assert!(bonding_duration > 0, "Bonding duration must be at least one era");
+
+        SessionsPerEra::set(self.sessions_per_era.unwrap_or(SESSIONS_PER_ERA));
+        BondingDuration::set(bonding_duration);
+        SlashDeferDuration::set(bonding_duration - 1);
+
+        let session_duration = self.session_duration.unwrap_or(SESSION_DURATION_IN_BLOCKS);
+        Period::set(session_duration);
+        Offset::set(session_duration + 1);
+
+        ExistentialDeposit::set(self.existential_deposit.unwrap_or(EXISTENTIAL_DEPOSIT));
+        GasMultiplier::set(common::GasMultiplier::ValuePerGas(
+            self.value_per_gas.unwrap_or(VALUE_PER_GAS),
+        ));
+        EraPayoutValue::set(self.era_payout.unwrap_or(ERA_PAYOUT));
+        VaraRewards::set(self.vara_rewards);
+
+        let mut storage = if self.genesis_cache_disabled {
+            self.build_genesis_storage()
+        } else {
+            cached_genesis_storage(self.genesis_key(), || self.build_genesis_storage())
+        };
+
+        // --- State over the genesis (optional) ---
+
+        if let Some(chain_state) = &self.chain_state {
+            storage.top.extend(chain_state.storage().clone());
+        }
+
+        if let Some(state) = &self.state {
+            storage.top.extend(state.storage().clone());
+        }
+
+        let mut ext: sp_io::TestExternalities = storage.into();
+
+        ext.execute_with(|| {
+            runtime::reset_runtime_state();
+            runtime::set_code_cache(self.code_cache);
+            runtime::set_gear_invariant_checks(self.gear_invariant_checks);
+            runtime::set_author_rotation(self.author_rotation);
+            runtime::set_auto_payout(self.auto_payout);
+            #[cfg(feature = "try-runtime")]
+            runtime::set_try_state_checks(self.try_state_checks);
+
+            let new_blk = 1;
+            System::set_block_number(new_blk);
+            runtime::on_initialize(new_blk);
+        });
+        
+        ext
+    }
 
-        let endowed_initial_authorities = self
+    /// Values that change the genesis storage, used as the genesis cache key
+    fn genesis_key(&self) -> Vec<u8> {
+        let initial_authorities: Vec<(AccountId, Option<u64>)> = self
             .initial_authorities
             .iter()
-            .map(|(initial_authority_address, _)| (*initial_authority_address, self.endowment));
-
-        let balances = endowed_accounts_iter
-            .chain(endowed_initial_authorities)
+            .map(|(authority_addr, session_key)| {
+                (authority_addr.clone(), session_key.as_ref().map(|key| key.0))
+            })
             .collect();
 
-        pallet_balances::GenesisConfig::<Test> { balances }
-            .assimilate_storage(&mut storage)
-            .expect("balances genesis");
+        (
+            initial_authorities,
+            &self.endowed_accounts,
+            self.endowment,
+            &self.balances,
+            self.vara_rewards.map(|config| config.pool_balance),
+            self.enable_sessions,
+            SessionsPerEra::get(),
+            BondingDuration::get(),
+            Period::get(),
+            ExistentialDeposit::get(),
+        )
+            .encode()
+    }
+
+    fn build_genesis_storage(&self) -> Storage {
+        let mut storage = system::GenesisConfig::<Test>::default()
+            .build_storage()
+            .expect("system genesis builds");
+
+        // --- Balances ---
 
+        let balances = self.genesis_balances();
+
+        pallet_balances::GenesisConfig::<Test> {
+            balances: balances
+                .iter()
+                .map(|(account, balance)| (account.clone(), *balance))
+                .collect(),
+        }
+        .assimilate_storage(&mut storage)
+        .expect("balances genesis");
 
         // --- Session (optional) ---
 
@@ -81,8 +341,8 @@ impl ExtBuilder {
             let keys = self.initial_authorities
                 .iter()
                 .map(|(authority_addr, session_key)| (
-                    *authority_addr,     // stash
-                    *authority_addr,     // controller
+                    authority_addr.clone(),     // stash
+                    authority_addr.clone(),     // controller
                     session_key
                         .clone()
                         .expect("Authorization without session key") // key
@@ -102,16 +362,16 @@ impl ExtBuilder {
         let stakers = self.initial_authorities
             .iter()
             .map(|(authority_addr, _)| (
-                    *authority_addr, // stash
-                    *authority_addr, // controller
-                    self.endowment,
+                    authority_addr.clone(), // stash
+                    authority_addr.clone(), // controller
+                    balances[authority_addr],
                     pallet_staking::StakerStatus::<AccountId>::Validator,
             ))
             .collect();
 
         let invulnerables = self.initial_authorities
             .iter()
-            .map(|(authority_addr, _)| *authority_addr)
+            .map(|(authority_addr, _)| authority_addr.clone())
             .collect();
 
         pallet_staking::GenesisConfig::<Test> {
@@ -125,14 +385,76 @@ impl ExtBuilder {
         .assimilate_storage(&mut storage)
         .expect("staking genesis");
 
-        let mut ext: sp_io::TestExternalities = storage.into();
+        storage
+    }
 
-        ext.execute_with(|| {
-            let new_blk = 1;
-            System::set_block_number(new_blk);
-            runtime::on_initialize(new_blk);
-        });
-        
-        ext
+    /// Balance of each account in the genesis, an account listed more than once (e.g. endowed
+    /// and authority) is funded once, custom balances replace the endowment
+    fn genesis_balances(&self) -> BTreeMap<AccountId, Balance> {
+        let mut balances: BTreeMap<AccountId, Balance> = self
+            .endowed_accounts
+            .iter()
+            .chain(
+                self.initial_authorities
+                    .iter()
+                    .map(|(authority_addr, _)| authority_addr),
+            )
+            .map(|account| (account.clone(), self.endowment))
+            .collect();
+
+        if let Some(config) = &self.vara_rewards {
+            balances.insert(rewards_pool_account(), config.pool_balance);
+        }
+
+        balances.extend(self.balances.iter().cloned());
+        balances.retain(|_, balance| *balance > 0);
+
+        let existential_deposit = ExistentialDeposit::get();
+
+        for (account, balance) in balances.iter() {
+            assert!(
+                *balance >= existential_deposit,
+                "Balance of account {account} ({balance}) is lower than the existential deposit ({existential_deposit})"
+            );
+        }
+
+        // Authorities bond all their balance, so it can't be zero
+        for (authority_addr, _) in self.initial_authorities.iter() {
+            assert!(
+                balances.contains_key(authority_addr),
+                "Authority {authority_addr} has no balance to bond"
+            );
+        }
+
+        balances
+    }
+}
+
+/// ## Genesis cache
+/// Genesis storage already built for each configuration, shared by all the tests (threads)
+/// so the genesis is built once and cloned in the next tests with the same configuration.
+fn cached_genesis_storage(key: Vec<u8>, build: impl FnOnce() -> Storage) -> Storage {
+    static GENESIS_CACHE: OnceLock<Mutex<HashMap<Vec<u8>, Storage>>> = OnceLock::new();
+
+    let cache = GENESIS_CACHE.get_or_init(Default::default);
+
+    let cached_storage = cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&key)
+        .cloned();
+
+    if let Some(storage) = cached_storage {
+        return storage;
     }
+
+    // Built without the lock, so a panic in the genesis does not block other tests
+    let storage = build();
+
+    cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(key, storage.clone());
+
+    storage
 }
@@ -8,11 +8,14 @@ mod proxy_helper;
 mod runtime_types;
 
 #[cfg(any(test, feature = "std"))]
-mod ext_builder;
+pub mod ext_builder;
 
 #[cfg(any(test, feature = "std"))]
 mod staking_helper;
 
+#[cfg(any(test, feature = "std"))]
+mod randomness_helper;
+
 #[cfg(any(test, feature = "std"))]
 pub mod runtime;
 
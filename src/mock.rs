@@ -4,10 +4,9 @@ use frame_election_provider_support::{
 };
 use frame_support::{
     construct_runtime, parameter_types,
-    traits::{ConstU32, ConstU64, FindAuthor},
+    traits::{ConstU32, ConstU64, FindAuthor, LockIdentifier},
     PalletId,
 };
-use frame_support_test::TestRandomness;
 use frame_system::{self as system};
 use pallet_session::historical::{self as pallet_session_historical};
 use sp_core::{crypto::key_types, H256};
@@ -19,11 +18,14 @@ use sp_runtime::{
 
 use pallet_gear_builtin::{bls12_381, proxy, staking, ActorWithId};
 
-use crate::staking_helper::{FixedEraPayout, OnChainSeqPhragmen};
+use crate::randomness_helper::TestRandomness;
+use crate::runtime::author_rotation_enabled;
+use crate::staking_helper::{OnChainSeqPhragmen, RewardsPool, TestEraPayout};
 
 use crate::types::{
-    AccountId, Balance, Block, BlockNumber, BLOCK_AUTHOR, EXISTENTIAL_DEPOSIT,
-    SESSION_DURATION_IN_BLOCKS, UNITS,
+    account_id, AccountId, Balance, Block, BlockNumber, VaraRewardsConfig, BLOCK_AUTHOR,
+    BONDING_DURATION, DEFAULT_BLOCK_GAS_LIMIT, ERA_PAYOUT, EXISTENTIAL_DEPOSIT,
+    MILLISECS_PER_BLOCK, SESSIONS_PER_ERA, SESSION_DURATION_IN_BLOCKS, VALUE_PER_GAS,
 };
 
 // Configure a mock runtime to test the pallet.
@@ -48,15 +50,19 @@ construct_runtime!(
     }
 );
 
+// Parameters with `static` can be overridden per test (ExtBuilder)
 parameter_types! {
     pub const BlockHashCount: u64 = 250;
-    pub const ExistentialDeposit: Balance = EXISTENTIAL_DEPOSIT;
+    pub static ExistentialDeposit: Balance = EXISTENTIAL_DEPOSIT;
     pub ElectionBoundsOnChain: ElectionBounds = ElectionBoundsBuilder::default().build();
 }
 
 parameter_types! {
-    pub const MinimumPeriod: u64 = 1_500;
-    pub const BlockGasLimit: u64 = 100_000_000_000_000; // 100_000_000_000_000 of gas limit
+    // Block time can be changed per test (runtime::set_block_time)
+    pub static BlockTime: u64 = MILLISECS_PER_BLOCK;
+    pub MinimumPeriod: u64 = BlockTime::get() / 2;
+    // Block gas limit can be changed per test (runtime::set_block_gas_limit)
+    pub static BlockGasLimit: u64 = DEFAULT_BLOCK_GAS_LIMIT; // 100_000_000_000_000 of gas limit
     pub const OutgoingLimit: u32 = 1024;
     pub const OutgoingBytesLimit: u32 = 64 * 1024 * 1024;
     pub ReserveThreshold: BlockNumber = 1;
@@ -67,19 +73,74 @@ parameter_types! {
     pub ResumeSessionDuration: BlockNumber = 1_000;
     pub const PerformanceMultiplier: u32 = 100;
     pub const BankPalletId: PalletId = PalletId(*b"py/gbank");
-    pub const GasMultiplier: common::GasMultiplier<Balance, u64> = common::GasMultiplier::ValuePerGas(100);
+    pub static GasMultiplier: common::GasMultiplier<Balance, u64> = common::GasMultiplier::ValuePerGas(VALUE_PER_GAS);
 }
 
 common::impl_pallet_system!(Test);
-common::impl_pallet_balances!(Test);
-common::impl_pallet_authorship!(Test, EventHandler = Staking);
+// Balances allow freezes (runtime::freeze), with the same ids as the locks
+impl pallet_balances::Config for Test {
+    type MaxLocks = ConstU32<50>;
+    type MaxReserves = ConstU32<50>;
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type DustRemoval = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+    type FreezeIdentifier = LockIdentifier;
+    type MaxFreezes = ConstU32<8>;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type RuntimeFreezeReason = RuntimeFreezeReason;
+}
+// Block author is an account (not a constant), so it works with both account types. With the
+// author rotation (runtime::set_author_rotation) the session validators author the blocks in turns
+pub struct TestBlockAuthor;
+impl FindAuthor<AccountId> for TestBlockAuthor {
+    fn find_author<'a, I>(_digests: I) -> Option<AccountId>
+    where
+        I: 'a + IntoIterator<Item = (sp_runtime::ConsensusEngineId, &'a [u8])>,
+    {
+        let validators = Session::validators();
+
+        if !author_rotation_enabled() || validators.is_empty() {
+            return Some(account_id(BLOCK_AUTHOR));
+        }
+
+        let index = System::block_number() as usize % validators.len();
+
+        validators.get(index).cloned()
+    }
+}
+
+impl pallet_authorship::Config for Test {
+    type FindAuthor = TestBlockAuthor;
+    type EventHandler = Staking;
+}
 // common::impl_pallet_timestamp!(Test);
+
+parameter_types! {
+    pub static SessionsPerEra: sp_staking::SessionIndex = SESSIONS_PER_ERA;
+    pub static BondingDuration: sp_staking::EraIndex = BONDING_DURATION;
+    pub static SlashDeferDuration: sp_staking::EraIndex = BONDING_DURATION - 1;
+    pub static EraPayoutValue: Balance = ERA_PAYOUT;
+    // Vara rewards model instead of the fixed payout (ExtBuilder::with_vara_rewards)
+    pub static VaraRewards: Option<VaraRewardsConfig> = None;
+    pub const StakingRewardsPalletId: PalletId = PalletId(*b"py/strwd");
+}
+
+// The static parameters are passed with the full path, so they are not shadowed by the defaults
+// of the macro
 common::impl_pallet_staking!(
     Test,
-    EraPayout = FixedEraPayout::<{ 100 * UNITS }>,
+    EraPayout = TestEraPayout,
+    Reward = RewardsPool,
     NextNewSession = Session,
     ElectionProvider = onchain::OnChainExecution<OnChainSeqPhragmen>,
     GenesisElectionProvider = onchain::OnChainExecution<OnChainSeqPhragmen>,
+    SessionsPerEra = crate::mock::SessionsPerEra,
+    BondingDuration = crate::mock::BondingDuration,
+    SlashDeferDuration = crate::mock::SlashDeferDuration,
 );
 
 pub struct TestSessionHandler;
@@ -99,8 +160,8 @@ impl pallet_session::SessionHandler<AccountId> for TestSessionHandler {
 }
 
 parameter_types! {
-    pub const Period: u64 = SESSION_DURATION_IN_BLOCKS;
-    pub const Offset: u64 = SESSION_DURATION_IN_BLOCKS + 1;
+    pub static Period: u64 = SESSION_DURATION_IN_BLOCKS;
+    pub static Offset: u64 = SESSION_DURATION_IN_BLOCKS + 1;
 }
 
 impl pallet_timestamp::Config for Test {
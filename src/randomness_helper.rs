@@ -0,0 +1,43 @@
+use core::marker::PhantomData;
+use frame_support::traits::Randomness;
+use frame_system::pallet_prelude::BlockNumberFor;
+use parity_scale_codec::{Decode, Encode};
+use sp_core::blake2_256;
+use sp_runtime::traits::{TrailingZeroInput, UniqueSaturatedInto};
+
+use crate::runtime_types::{RANDOM_OUTPUT, RANDOM_OUTPUTS_BY_BLOCK, RANDOM_SEED};
+
+// Randomness used by Gear (random syscalls), it can be controlled by the tests:
+// 1. Output set for the current block (runtime::set_random_output_at)
+// 2. Fixed output (runtime::set_random_output)
+// 3. Output derived from a seed, the block and the subject (runtime::set_random_seed)
+// 4. The subject padded with zeros (same as frame_support_test::TestRandomness)
+pub struct TestRandomness<T>(PhantomData<T>);
+
+impl<Output: Decode + Default, T: frame_system::Config> Randomness<Output, BlockNumberFor<T>>
+    for TestRandomness<T>
+{
+    fn random(subject: &[u8]) -> (Output, BlockNumberFor<T>) {
+        let block_number = frame_system::Pallet::<T>::block_number();
+        let block: u64 = block_number.unique_saturated_into();
+
+        let block_output =
+            RANDOM_OUTPUTS_BY_BLOCK.with(|outputs| outputs.borrow().get(&block).copied());
+
+        let random_bytes = block_output
+            .or_else(|| RANDOM_OUTPUT.with(|output| *output.borrow()))
+            .map(|output| output.to_vec())
+            .or_else(|| {
+                RANDOM_SEED.with(|seed| {
+                    seed.borrow()
+                        .map(|seed| blake2_256(&(seed, block, subject).encode()).to_vec())
+                })
+            })
+            .unwrap_or_else(|| subject.to_vec());
+
+        (
+            Output::decode(&mut TrailingZeroInput::new(&random_bytes)).unwrap_or_default(),
+            block_number,
+        )
+    }
+}
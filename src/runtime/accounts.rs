@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use common::Origin;
+use gprimitives::ActorId;
+use sp_core::{blake2_256, H256};
+
+use crate::mock::System;
+use crate::runtime_types::ACCOUNT_NAMES;
+use crate::types::{actorid32::ActorId32, AccountId, Balance};
+
+/// ## Test account
+/// Named account of the tests, the id is derived from the name (the same name always gives the
+/// same account), and the name is registered so reports and event dumps print it instead of the id.
+///
+/// ```ignore
+/// let alice = account("alice");
+/// let bob = account("bob");
+///
+/// new_test_ext(vec![alice.id(), bob.id()]).execute_with(|| {
+///     // test logic ...
+/// });
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TestAccount {
+    id: AccountId,
+    name: String,
+}
+
+impl TestAccount {
+    /// Creates the account with the id derived from the name
+    pub fn new(name: &str) -> Self {
+        let id = AccountId::from_origin(H256(blake2_256(name.as_bytes())));
+
+        Self::named(id, name)
+    }
+
+    /// Gives a name to an existing account (e.g. `TestAccount::named(10, "validator_1")`)
+    pub fn named(id: AccountId, name: &str) -> Self {
+        ACCOUNT_NAMES.with(|names| names.borrow_mut().insert(id.clone(), name.to_string()));
+
+        Self {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn id(&self) -> AccountId {
+        self.id.clone()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Account as the ActorId used by the Gear programs (e.g. `msg::source()`)
+    pub fn actor_id(&self) -> ActorId {
+        ActorId::from_origin(self.id().into_origin())
+    }
+
+    pub fn actor_id32(&self) -> ActorId32 {
+        ActorId32::from(self.actor_id())
+    }
+
+    /// Free balance of the account (inside the test externalities)
+    pub fn free_balance(&self) -> Balance {
+        System::account(&self.id).data.free
+    }
+}
+
+impl From<TestAccount> for AccountId {
+    fn from(account: TestAccount) -> Self {
+        account.id
+    }
+}
+
+impl From<&TestAccount> for AccountId {
+    fn from(account: &TestAccount) -> Self {
+        account.id()
+    }
+}
+
+impl core::fmt::Display for TestAccount {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// ## Named account
+/// Returns the account of the name, e.g. `account("alice")`
+pub fn account(name: &str) -> TestAccount {
+    TestAccount::new(name)
+}
+
+/// ## Named accounts
+/// Returns the accounts of the names, in the same order
+pub fn accounts(names: &[&str]) -> Vec<TestAccount> {
+    names.iter().map(|name| TestAccount::new(name)).collect()
+}
+
+/// ## Validator accounts
+/// Returns `count` accounts named `validator_1`, `validator_2`, etc, with their session keys
+/// (`validator_1_session`, ...), ready for `new_test_ext_with_authorities_and_sessions`:
+///
+/// ```ignore
+/// let validators = validators(2);
+/// let authorities = validators
+///     .iter()
+///     .map(|(stash, session)| (stash.id(), session.id()))
+///     .collect();
+/// ```
+pub fn validators(count: u32) -> Vec<(TestAccount, TestAccount)> {
+    (1..=count)
+        .map(|index| {
+            (
+                TestAccount::new(&format!("validator_{index}")),
+                TestAccount::new(&format!("validator_{index}_session")),
+            )
+        })
+        .collect()
+}
+
+/// Removes the names of all the accounts
+pub fn clear_account_names() {
+    ACCOUNT_NAMES.with(|names| names.borrow_mut().clear());
+}
+
+// Fields of the events that hold accounts
+const ACCOUNT_FIELDS: &[&str] = &[
+    "who",
+    "from",
+    "to",
+    "account",
+    "stash",
+    "validator",
+    "nominator",
+    "controller",
+    "author",
+    "owner",
+    "beneficiary",
+];
+
+// Replaces the ids of the named accounts with their names in the account fields of the debug
+// output (`who: 10`, `stash: ActorId32(kG..)`), other values with the same digits are kept
+pub(crate) fn with_account_names(text: &str) -> String {
+    let names: HashMap<String, String> = ACCOUNT_NAMES.with(|names| {
+        names
+            .borrow()
+            .iter()
+            .map(|(id, name)| (id.to_string(), name.clone()))
+            .collect()
+    });
+
+    if names.is_empty() {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut word = String::new();
+    // Field of the value being read (`name: value`)
+    let mut field = String::new();
+
+    for char in text.chars().map(Some).chain(core::iter::once(None)) {
+        if let Some(char) = char.filter(|char| char.is_ascii_alphanumeric() || *char == '_') {
+            word.push(char);
+            continue;
+        }
+
+        match names.get(&word) {
+            Some(name) if ACCOUNT_FIELDS.contains(&field.as_str()) => result.push_str(name),
+            _ => result.push_str(&word),
+        }
+
+        match char {
+            Some(':') => field = core::mem::take(&mut word),
+            Some(',' | '{' | '}' | '[' | ']') => field.clear(),
+            _ => {}
+        }
+
+        word.clear();
+
+        if let Some(char) = char {
+            result.push(char);
+        }
+    }
+
+    result
+}
@@ -0,0 +1,347 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+use common::Origin;
+use gear_core::ids::MessageId;
+use gprimitives::ActorId;
+use pallet_staking::RewardDestination;
+
+use crate::contract::Contract;
+use crate::mock::{GearBank, RuntimeEvent, System, Test};
+use crate::types::{AccountId, Balance, BalanceDelta, BlockHookId, QueueOf};
+
+use super::{
+    add_before_block_hook, add_events_reset_hook, block_author, in_transaction, remove_block_hook,
+};
+
+/// ## Balance tracker
+/// Takes the balances of a set of accounts and contracts when it is created, and tracks the
+/// events and the messages of the next blocks, so the change of each balance can be split into
+/// gas paid, value sent/received, transfers, staking locks and rewards (`BalanceDelta`):
+///
+/// ```ignore
+/// let tracker = BalanceTracker::new([SIGNER]).with_contract(&contract);
+///
+/// // send messages, run blocks ...
+///
+/// let signer = tracker.delta(SIGNER);
+/// assert_eq!(signer.value_sent, 100 * ONE_TOKEN);
+/// tracker.assert_free_delta(SIGNER, -(100 * ONE_TOKEN as i128) - signer.gas_paid);
+/// ```
+///
+/// The tracker reads the runtime events before they are reset (`reset_system_events`, also called
+/// by `send_recv` and `send_check_result`), so the commands can be used while it is tracking.
+///
+/// The events of the blocks run inside a storage transaction (queries, `with_rollback`) are read
+/// after the transaction ends, so the rolled back changes are not counted.
+///
+/// > Note: value claimed from the mailbox is counted as a gas refund.
+pub struct BalanceTracker {
+    state: Rc<RefCell<TrackerState>>,
+    hook: BlockHookId,
+    reset_hook: BlockHookId,
+}
+
+#[derive(Default)]
+struct TrackerState {
+    initial: BTreeMap<AccountId, AccountBalances>,
+    users: BTreeMap<ActorId, AccountId>,
+    contracts: BTreeSet<AccountId>,
+    deltas: BTreeMap<AccountId, TrackedDelta>,
+    next_event: usize,
+    seen_messages: BTreeSet<MessageId>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct AccountBalances {
+    free: Balance,
+    frozen: Balance,
+    reserved: Balance,
+}
+
+// Causes of the balance change, the gas paid by users is calculated from the gear bank transfers
+#[derive(Clone, Copy, Default)]
+struct TrackedDelta {
+    to_bank: Balance,
+    from_bank: Balance,
+    value_sent: Balance,
+    value_received: Balance,
+    transferred_in: Balance,
+    transferred_out: Balance,
+    rewards: Balance,
+    slashed: Balance,
+    fees_received: Balance,
+}
+
+impl BalanceTracker {
+    /// Starts tracking the accounts (users)
+    pub fn new<A: Into<AccountId>>(accounts: impl IntoIterator<Item = A>) -> Self {
+        let state = Rc::new(RefCell::new(TrackerState {
+            next_event: System::event_count() as usize,
+            ..Default::default()
+        }));
+
+        {
+            let mut state = state.borrow_mut();
+
+            for account in accounts {
+                state.track(account.into(), false);
+            }
+
+            // Messages already in the queue were paid before the tracker started
+            state.seen_messages = queued_messages().map(|(id, ..)| id).collect();
+        }
+
+        let hook_state = state.clone();
+        let hook = add_before_block_hook(move |_| {
+            if !in_transaction() {
+                hook_state.borrow_mut().sync();
+            }
+        });
+
+        // Events reset inside a transaction are restored if it is rolled back
+        let reset_state = state.clone();
+        let reset_hook = add_events_reset_hook(move || {
+            if in_transaction() {
+                return;
+            }
+
+            let mut state = reset_state.borrow_mut();
+
+            state.sync();
+            state.next_event = 0;
+        });
+
+        Self {
+            state,
+            hook,
+            reset_hook,
+        }
+    }
+
+    /// Starts tracking the contract account
+    pub fn with_contract(self, contract: &Contract) -> Self {
+        self.state.borrow_mut().track(contract.account(), true);
+        self
+    }
+
+    /// Starts tracking the account
+    pub fn with_account(self, account: impl Into<AccountId>) -> Self {
+        self.state.borrow_mut().track(account.into(), false);
+        self
+    }
+
+    /// ## Balance delta of an account
+    /// Panics if the account is not tracked.
+    pub fn delta(&self, account: impl Into<AccountId>) -> BalanceDelta {
+        let account = account.into();
+        let mut state = self.state.borrow_mut();
+
+        state.sync();
+        state.delta(account)
+    }
+
+    /// Balance deltas of all the tracked accounts
+    pub fn deltas(&self) -> Vec<BalanceDelta> {
+        let mut state = self.state.borrow_mut();
+
+        state.sync();
+
+        let accounts: Vec<AccountId> = state.initial.keys().cloned().collect();
+        accounts
+            .into_iter()
+            .map(|account| state.delta(account))
+            .collect()
+    }
+
+    /// Breakdown of the balance deltas of all the tracked accounts
+    pub fn report(&self) -> String {
+        self.deltas()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
+    /// ## Assert the free balance delta
+    /// Fails with the breakdown of the delta if the free balance did not change by `expected`.
+    pub fn assert_free_delta(&self, account: impl Into<AccountId>, expected: i128) {
+        let delta = self.delta(account);
+
+        assert_eq!(
+            delta.free, expected,
+            "Unexpected free balance delta ({expected:+} expected)\n{delta}"
+        );
+    }
+
+    /// ## Assert that all the deltas are explained
+    /// Fails if the balance of a tracked account changed by an unknown cause.
+    pub fn assert_explained(&self) {
+        for delta in self.deltas() {
+            assert_eq!(delta.unexplained(), 0, "Unexplained balance delta\n{delta}");
+        }
+    }
+}
+
+impl Drop for BalanceTracker {
+    fn drop(&mut self) {
+        remove_block_hook(self.hook);
+        remove_block_hook(self.reset_hook);
+    }
+}
+
+impl TrackerState {
+    fn track(&mut self, account: AccountId, contract: bool) {
+        let account_data = System::account(&account).data;
+
+        self.initial.insert(
+            account.clone(),
+            AccountBalances {
+                free: account_data.free,
+                frozen: account_data.frozen,
+                reserved: account_data.reserved,
+            },
+        );
+        self.deltas.insert(account.clone(), TrackedDelta::default());
+
+        if contract {
+            self.contracts.insert(account);
+        } else {
+            self.users
+                .insert(ActorId::from_origin(account.clone().into_origin()), account);
+        }
+    }
+
+    // Reads the events and the queued messages since the last sync
+    fn sync(&mut self) {
+        // Events were reset without `reset_system_events` (`System::reset_events`), the events
+        // emitted before the reset are lost, the tracker continues from the first event
+        if (System::event_count() as usize) < self.next_event {
+            self.next_event = 0;
+        }
+
+        let events: Vec<RuntimeEvent> = System::read_events_no_consensus()
+            .skip(self.next_event)
+            .map(|record| record.event.clone())
+            .collect();
+
+        self.next_event += events.len();
+
+        let bank = GearBank::bank_address();
+        let author = block_author();
+
+        for event in events {
+            match event {
+                RuntimeEvent::Balances(pallet_balances::Event::Transfer { from, to, amount }) => {
+                    if let Some(delta) = self.deltas.get_mut(&from) {
+                        if to == bank {
+                            delta.to_bank += amount;
+                        } else {
+                            delta.transferred_out += amount;
+                        }
+                    }
+
+                    if let Some(delta) = self.deltas.get_mut(&to) {
+                        if from != bank {
+                            delta.transferred_in += amount;
+                        } else if author.as_ref() == Some(&to) && !self.contracts.contains(&to) {
+                            delta.fees_received += amount;
+                        } else {
+                            delta.from_bank += amount;
+                        }
+                    }
+                }
+                RuntimeEvent::Balances(pallet_balances::Event::Slashed { who, amount }) => {
+                    if let Some(delta) = self.deltas.get_mut(&who) {
+                        delta.slashed += amount;
+                    }
+                }
+                RuntimeEvent::Staking(pallet_staking::Event::Rewarded {
+                    stash,
+                    dest,
+                    amount,
+                }) => {
+                    let receiver = match dest {
+                        RewardDestination::Account(account) => Some(account),
+                        RewardDestination::None => None,
+                        _ => Some(stash),
+                    };
+
+                    if let Some(delta) = receiver.and_then(|account| self.deltas.get_mut(&account))
+                    {
+                        delta.rewards += amount;
+                    }
+                }
+                RuntimeEvent::Gear(pallet_gear::Event::UserMessageSent {
+                    message,
+                    expiration: None,
+                }) => {
+                    let receiver = self.users.get(&message.destination());
+
+                    if let Some(delta) = receiver.and_then(|account| self.deltas.get_mut(account)) {
+                        delta.value_received += message.value();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Value sent by the users is taken from their messages in the queue
+        for (id, source, value) in queued_messages() {
+            if !self.seen_messages.insert(id) {
+                continue;
+            }
+
+            let sender = self.users.get(&source);
+
+            if let Some(delta) = sender.and_then(|account| self.deltas.get_mut(account)) {
+                delta.value_sent += value;
+            }
+        }
+    }
+
+    fn delta(&self, account: AccountId) -> BalanceDelta {
+        let initial = self
+            .initial
+            .get(&account)
+            .unwrap_or_else(|| panic!("Account {account} is not tracked"));
+        let tracked = self.deltas.get(&account).copied().unwrap_or_default();
+        let account_data = System::account(&account).data;
+
+        let (gas_paid, value_sent, value_received) = if self.contracts.contains(&account) {
+            // Contracts don't pay gas, the value goes through the gear bank
+            (0, tracked.to_bank, tracked.from_bank)
+        } else {
+            // Deposited in the gear bank = gas paid + value sent - value received
+            let deposited = tracked.to_bank as i128 - tracked.from_bank as i128;
+            let gas_paid = deposited - tracked.value_sent as i128 + tracked.value_received as i128;
+
+            (gas_paid, tracked.value_sent, tracked.value_received)
+        };
+
+        BalanceDelta {
+            account,
+            free: account_data.free as i128 - initial.free as i128,
+            frozen: account_data.frozen as i128 - initial.frozen as i128,
+            reserved: account_data.reserved as i128 - initial.reserved as i128,
+            gas_paid,
+            value_sent,
+            value_received,
+            transferred_in: tracked.transferred_in,
+            transferred_out: tracked.transferred_out,
+            rewards: tracked.rewards,
+            slashed: tracked.slashed,
+            fees_received: tracked.fees_received,
+        }
+    }
+}
+
+// Messages in the Gear queue: (id, source, value)
+fn queued_messages() -> impl Iterator<Item = (MessageId, ActorId, Balance)> {
+    QueueOf::<Test>::iter_values().map(|node| {
+        let dispatch = node.value;
+
+        (dispatch.id(), dispatch.source(), dispatch.value())
+    })
+}
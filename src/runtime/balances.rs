@@ -0,0 +1,128 @@
+use frame_support::{
+    assert_ok,
+    pallet_prelude::DispatchResult,
+    traits::{
+        fungible::MutateFreeze, Currency, Imbalance, LockIdentifier, LockableCurrency,
+        ReservableCurrency, WithdrawReasons,
+    },
+};
+
+use crate::mock::{Balances, RuntimeOrigin, Test};
+use crate::types::{AccountId, Balance};
+
+/// ## Set the balance of an account
+/// Sets the free balance of the account (user or contract account), e.g. to simulate external
+/// funding. If the balance is lower than the existential deposit the account is removed.
+pub fn set_balance(account: impl Into<AccountId>, free: Balance) {
+    assert_ok!(Balances::force_set_balance(
+        RuntimeOrigin::root(),
+        account.into(),
+        free
+    ));
+}
+
+/// ## Transfer tokens
+/// Transfers `amount` from `from` to `to` (the `from` account can be removed), returns the error
+/// of the transfer if it failed (e.g. the balance is locked).
+pub fn transfer(
+    from: impl Into<AccountId>,
+    to: impl Into<AccountId>,
+    amount: Balance,
+) -> DispatchResult {
+    Balances::transfer_allow_death(RuntimeOrigin::signed(from.into()), to.into(), amount)
+        .map(|_| ())
+        .map_err(|error| error.error)
+}
+
+/// ## Slash an account
+/// Slashes up to `amount` of the account (free balance first, then the reserved balance), the
+/// slashed tokens are burned. Returns the slashed amount.
+pub fn slash(account: impl Into<AccountId>, amount: Balance) -> Balance {
+    let account = account.into();
+
+    // The free balance slash keeps the existential deposit if the account can't be removed
+    let (free_imbalance, remaining) = <Balances as Currency<AccountId>>::slash(&account, amount);
+    let (reserved_imbalance, _) =
+        <Balances as ReservableCurrency<AccountId>>::slash_reserved(&account, remaining);
+
+    free_imbalance.peek() + reserved_imbalance.peek()
+}
+
+/// ## Set a lock
+/// Locks `amount` of the account with the lock id (a lock with the same id is replaced), the
+/// staking lock is `STAKING_LOCK_ID`:
+///
+/// ```ignore
+/// set_lock(*b"vesting ", SIGNER, 100 * ONE_TOKEN);
+/// assert_eq!(lock(*b"vesting ", SIGNER), Some(100 * ONE_TOKEN));
+/// ```
+pub fn set_lock(id: LockIdentifier, account: impl Into<AccountId>, amount: Balance) {
+    <Balances as LockableCurrency<AccountId>>::set_lock(
+        id,
+        &account.into(),
+        amount,
+        WithdrawReasons::all(),
+    );
+}
+
+/// Removes the lock of the account with the lock id
+pub fn remove_lock(id: LockIdentifier, account: impl Into<AccountId>) {
+    <Balances as LockableCurrency<AccountId>>::remove_lock(id, &account.into());
+}
+
+/// Returns the amount locked by the lock id, if the account has the lock
+pub fn lock(id: LockIdentifier, account: impl Into<AccountId>) -> Option<Balance> {
+    locks(account)
+        .into_iter()
+        .find_map(|(lock_id, amount)| (lock_id == id).then_some(amount))
+}
+
+/// Returns the locks of the account: (lock id, amount)
+pub fn locks(account: impl Into<AccountId>) -> Vec<(LockIdentifier, Balance)> {
+    pallet_balances::Locks::<Test>::get(account.into())
+        .into_iter()
+        .map(|lock| (lock.id, lock.amount))
+        .collect()
+}
+
+/// ## Freeze balance
+/// Freezes `amount` of the account with the freeze id (a freeze with the same id is replaced).
+/// Like locks, the frozen balance can't be transferred, but it can be used for staking.
+pub fn freeze(
+    id: LockIdentifier,
+    account: impl Into<AccountId>,
+    amount: Balance,
+) -> DispatchResult {
+    <Balances as MutateFreeze<AccountId>>::set_freeze(&id, &account.into(), amount)
+}
+
+/// Removes the freeze of the account with the freeze id
+pub fn thaw(id: LockIdentifier, account: impl Into<AccountId>) -> DispatchResult {
+    <Balances as MutateFreeze<AccountId>>::thaw(&id, &account.into())
+}
+
+/// Returns the freezes of the account: (freeze id, amount)
+pub fn freezes(account: impl Into<AccountId>) -> Vec<(LockIdentifier, Balance)> {
+    pallet_balances::Freezes::<Test>::get(account.into())
+        .into_iter()
+        .map(|freeze| (freeze.id, freeze.amount))
+        .collect()
+}
+
+/// ## Reserve balance
+/// Moves `amount` of the free balance of the account to its reserved balance, fails if the free
+/// balance is not enough.
+pub fn reserve(account: impl Into<AccountId>, amount: Balance) -> DispatchResult {
+    <Balances as ReservableCurrency<AccountId>>::reserve(&account.into(), amount)
+}
+
+/// Moves up to `amount` of the reserved balance back to the free balance, returns the amount
+/// that could not be unreserved
+pub fn unreserve(account: impl Into<AccountId>, amount: Balance) -> Balance {
+    <Balances as ReservableCurrency<AccountId>>::unreserve(&account.into(), amount)
+}
+
+/// Returns the reserved balance of the account
+pub fn reserved_balance(account: impl Into<AccountId>) -> Balance {
+    <Balances as ReservableCurrency<AccountId>>::reserved_balance(&account.into())
+}
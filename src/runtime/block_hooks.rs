@@ -0,0 +1,154 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::mock::System;
+use crate::runtime_types::*;
+use crate::types::{BlockHookId, RuntimeEvent};
+
+fn next_block_hook_id() -> BlockHookId {
+    NEXT_BLOCK_HOOK_ID.with(|next_id| {
+        let mut next_id = next_id.borrow_mut();
+        *next_id += 1;
+
+        BlockHookId(*next_id)
+    })
+}
+
+/// ## Add a hook before each block
+/// The hook is called with the block number before the block is executed (by `run_to_next_block`,
+/// `run_for_n_blocks`, `fast_forward_blocks`, etc), until it is removed with `remove_block_hook`
+/// or the test ends.
+pub fn add_before_block_hook(hook: impl FnMut(u64) + 'static) -> BlockHookId {
+    let id = next_block_hook_id();
+
+    BEFORE_BLOCK_HOOKS.with(|hooks| hooks.borrow_mut().push((id, Rc::new(RefCell::new(hook)))));
+
+    id
+}
+
+/// ## Add a hook after each block
+/// The hook is called with the block number and the events emitted in the block after the block
+/// is executed, so invariants can be checked in every block. Example:
+///
+/// ```ignore
+/// add_after_block_hook(move |_block, _events| {
+///     assert!(contract.free_balance() >= existential_deposit());
+/// });
+/// ```
+pub fn add_after_block_hook(hook: impl FnMut(u64, &[RuntimeEvent]) + 'static) -> BlockHookId {
+    let id = next_block_hook_id();
+
+    AFTER_BLOCK_HOOKS.with(|hooks| hooks.borrow_mut().push((id, Rc::new(RefCell::new(hook)))));
+
+    id
+}
+
+/// ## Remove a block hook
+/// Returns false if the hook was already removed.
+pub fn remove_block_hook(id: BlockHookId) -> bool {
+    let removed_before = BEFORE_BLOCK_HOOKS.with(|hooks| {
+        let mut hooks = hooks.borrow_mut();
+        let hooks_len = hooks.len();
+        hooks.retain(|(hook_id, _)| *hook_id != id);
+
+        hooks.len() != hooks_len
+    });
+
+    let removed_after = AFTER_BLOCK_HOOKS.with(|hooks| {
+        let mut hooks = hooks.borrow_mut();
+        let hooks_len = hooks.len();
+        hooks.retain(|(hook_id, _)| *hook_id != id);
+
+        hooks.len() != hooks_len
+    });
+
+    let removed_reset = EVENTS_RESET_HOOKS.with(|hooks| {
+        let mut hooks = hooks.borrow_mut();
+        let hooks_len = hooks.len();
+        hooks.retain(|(hook_id, _)| *hook_id != id);
+
+        hooks.len() != hooks_len
+    });
+
+    removed_before || removed_after || removed_reset
+}
+
+/// Removes all the block hooks
+pub fn clear_block_hooks() {
+    BEFORE_BLOCK_HOOKS.with(|hooks| hooks.borrow_mut().clear());
+    AFTER_BLOCK_HOOKS.with(|hooks| hooks.borrow_mut().clear());
+    EVENTS_RESET_HOOKS.with(|hooks| hooks.borrow_mut().clear());
+}
+
+// Adds a hook called before the events are reset (`reset_system_events`), so the helpers that read
+// the events (balance tracker) don't lose the events emitted since they last read them. It is
+// removed with `remove_block_hook`.
+pub(crate) fn add_events_reset_hook(hook: impl FnMut() + 'static) -> BlockHookId {
+    let id = next_block_hook_id();
+
+    EVENTS_RESET_HOOKS.with(|hooks| hooks.borrow_mut().push((id, Rc::new(RefCell::new(hook)))));
+
+    id
+}
+
+// Marks the first event of the block that starts, called before the block is initialized
+pub(crate) fn mark_block_start() {
+    BLOCK_FIRST_EVENT.with(|first| *first.borrow_mut() = System::event_count() as usize);
+}
+
+pub(crate) fn reset_block_start() {
+    BLOCK_FIRST_EVENT.with(|first| *first.borrow_mut() = 0);
+}
+
+// Hooks are cloned before calling them, so a hook can add or remove hooks
+pub(crate) fn run_before_block_hooks(block: u64) {
+    let hooks: Vec<BeforeBlockHook> = BEFORE_BLOCK_HOOKS.with(|hooks| {
+        hooks
+            .borrow()
+            .iter()
+            .map(|(_, hook)| hook.clone())
+            .collect()
+    });
+
+    for hook in hooks {
+        (hook.borrow_mut())(block);
+    }
+}
+
+pub(crate) fn run_after_block_hooks(block: u64) {
+    let hooks: Vec<AfterBlockHook> = AFTER_BLOCK_HOOKS.with(|hooks| {
+        hooks
+            .borrow()
+            .iter()
+            .map(|(_, hook)| hook.clone())
+            .collect()
+    });
+
+    if hooks.is_empty() {
+        return;
+    }
+
+    let first_event = BLOCK_FIRST_EVENT.with(|first| *first.borrow());
+    let events: Vec<RuntimeEvent> = System::read_events_no_consensus()
+        .skip(first_event)
+        .map(|record| record.event.clone())
+        .collect();
+
+    for hook in hooks {
+        (hook.borrow_mut())(block, &events);
+    }
+}
+
+pub(crate) fn run_events_reset_hooks() {
+    let hooks: Vec<EventsResetHook> = EVENTS_RESET_HOOKS.with(|hooks| {
+        hooks
+            .borrow()
+            .iter()
+            .map(|(_, hook)| hook.clone())
+            .collect()
+    });
+
+    for hook in hooks {
+        (hook.borrow_mut())();
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use parity_scale_codec::Encode;
+use serde_json::Value;
+use sp_core::{blake2_128, bytes::from_hex, twox_128, twox_64};
+
+use crate::types::AccountId;
+
+/// ## Chain state
+/// Raw key/value storage exported from a node (e.g. the `genesis.raw.top` of a raw chain spec,
+/// or the result of `state_getPairs`), it can be loaded over the genesis of a test with
+/// `ExtBuilder::with_chain_state`, so tests can run against real balances, validators, programs, etc.
+///
+/// Chain accounts are 32 bytes long, accounts used in the tests must be remapped to test (u64)
+/// accounts with `remap_account`, entries of accounts that are not remapped can not be used.
+#[derive(Clone, Debug, Default)]
+pub struct ChainState {
+    storage: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl ChainState {
+    /// ## Load a chain state file
+    /// The file is a json with hex keys and values, supported formats:
+    /// - Object: `{ "0x..": "0x..", ... }`
+    /// - Raw chain spec: `{ "genesis": { "raw": { "top": { "0x..": "0x..", ... } } } }`
+    /// - Pairs: `[ ["0x..", "0x.."], ... ]`
+    ///
+    /// Special keys (that start with `:`, like `:code`) are skipped.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::read(path)?;
+        let json: Value = serde_json::from_slice(&file).map_err(invalid_data)?;
+
+        let pairs: Vec<(&str, &Value)> = match json.pointer("/genesis/raw/top").unwrap_or(&json) {
+            Value::Object(entries) => entries
+                .iter()
+                .map(|(key, value)| (key.as_str(), value))
+                .collect(),
+            Value::Array(entries) => entries
+                .iter()
+                .map(|pair| match pair.as_array().map(Vec::as_slice) {
+                    Some([Value::String(key), value]) => Ok((key.as_str(), value)),
+                    _ => Err(invalid_data("chain state pairs must be [key, value]")),
+                })
+                .collect::<std::io::Result<_>>()?,
+            _ => return Err(invalid_data("unsupported chain state format")),
+        };
+
+        let mut storage = BTreeMap::new();
+
+        for (key, value) in pairs {
+            let key = hex_bytes(key)?;
+
+            if key.starts_with(b":") {
+                continue;
+            }
+
+            let value = value
+                .as_str()
+                .ok_or_else(|| invalid_data("chain state values must be hex strings"))?;
+
+            storage.insert(key, hex_bytes(value)?);
+        }
+
+        Ok(Self { storage })
+    }
+
+    /// ## Keep some pallets
+    /// Keeps only the storage of the given pallets (names as in the runtime, e.g. "Balances").
+    pub fn pallets(mut self, pallets: &[&str]) -> Self {
+        let prefixes: Vec<[u8; 16]> = pallets
+            .iter()
+            .map(|pallet| twox_128(pallet.as_bytes()))
+            .collect();
+
+        self.storage
+            .retain(|key, _| prefixes.iter().any(|prefix| key.starts_with(prefix)));
+
+        self
+    }
+
+    /// ## Remap an account
+    /// Replaces the chain account (32 bytes) with the test account in every key and value,
+    /// including the hashed keys of maps (`Blake2_128Concat` and `Twox64Concat`).
+    ///
+    /// > Note: the values are not decoded, any 32 bytes equal to the chain account are replaced
+    /// > (e.g. a hash or a program id with the same bytes). It is unlikely with real accounts,
+    /// > but chain accounts with repeated bytes (like `[1; 32]`) can match other values.
+    pub fn remap_account(mut self, chain_account: [u8; 32], test_account: AccountId) -> Self {
+        let test_account = test_account.encode();
+
+        let key_replacements = [
+            (
+                [blake2_128(&chain_account).as_slice(), &chain_account].concat(),
+                [blake2_128(&test_account).as_slice(), &test_account].concat(),
+            ),
+            (
+                [twox_64(&chain_account).as_slice(), &chain_account].concat(),
+                [twox_64(&test_account).as_slice(), &test_account].concat(),
+            ),
+            (chain_account.to_vec(), test_account.clone()),
+        ];
+
+        self.storage = self
+            .storage
+            .into_iter()
+            .map(|(key, value)| {
+                let key = key_replacements
+                    .iter()
+                    .fold(key, |key, (from, to)| replace_all(&key, from, to));
+
+                (key, replace_all(&value, &chain_account, &test_account))
+            })
+            .collect();
+
+        self
+    }
+
+    pub(crate) fn storage(&self) -> &BTreeMap<Vec<u8>, Vec<u8>> {
+        &self.storage
+    }
+
+    /// Number of storage entries in the chain state
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Returns true if the chain state has no storage entries
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Error {
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+fn hex_bytes(value: &str) -> std::io::Result<Vec<u8>> {
+    from_hex(value).map_err(|error| invalid_data(format!("invalid hex {value}: {error:?}")))
+}
+
+fn replace_all(bytes: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    if from.is_empty() || bytes.len() < from.len() {
+        return bytes.to_vec();
+    }
+
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index..].starts_with(from) {
+            result.extend_from_slice(to);
+            index += from.len();
+        } else {
+            result.push(bytes[index]);
+            index += 1;
+        }
+    }
+
+    result
+}
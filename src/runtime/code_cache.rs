@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use frame_support::dispatch::DispatchResultWithPostInfo;
+use gear_core::ids::{prelude::CodeIdExt, CodeId};
+use sp_core::twox_128;
+
+use crate::mock::{Gear, RuntimeOrigin};
+use crate::runtime_types::CODE_CACHE_ENABLED;
+use crate::types::AccountId;
+use crate::utils::storage_keys_with_prefix;
+
+// GearProgram storage entries of a code (original code, instrumented code, metadata, etc)
+type CodeEntries = Vec<(Vec<u8>, Vec<u8>)>;
+
+struct CachedCode {
+    entries: CodeEntries,
+    // Uploads that used the cached code
+    hits: u32,
+}
+
+fn code_cache() -> &'static Mutex<HashMap<CodeId, CachedCode>> {
+    static CODE_CACHE: OnceLock<Mutex<HashMap<CodeId, CachedCode>>> = OnceLock::new();
+
+    CODE_CACHE.get_or_init(Default::default)
+}
+
+/// ## Enable the code cache
+/// With the code cache enabled, the first upload of a wasm validates and instruments it as usual,
+/// and its code storage is saved for the whole test process. Next uploads of the same wasm
+/// (in any test) copy the saved code storage and create the program from it, without validate
+/// and instrument the code again.
+///
+/// > Note: when the code is taken from the cache, the `CodeChanged` event is not emitted.
+pub fn set_code_cache(enabled: bool) {
+    CODE_CACHE_ENABLED.with(|value| *value.borrow_mut() = enabled)
+}
+
+pub fn code_cache_enabled() -> bool {
+    CODE_CACHE_ENABLED.with(|value| *value.borrow())
+}
+
+/// Removes all the codes saved in the code cache
+pub fn clear_code_cache() {
+    code_cache()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+/// Returns true if the code is saved in the code cache
+pub fn code_cached(code_id: CodeId) -> bool {
+    code_cache()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains_key(&code_id)
+}
+
+/// Returns the number of uploads that used the cached code (0 if the code is not cached)
+pub fn code_cache_hits(code_id: CodeId) -> u32 {
+    code_cache()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&code_id)
+        .map_or(0, |code| code.hits)
+}
+
+// Code maps in GearProgram use the `Identity` hasher, so their keys are the
+// storage prefix (32 bytes) followed by the code id.
+fn code_storage_entries(code_id: CodeId) -> CodeEntries {
+    storage_keys_with_prefix(&twox_128(b"GearProgram"))
+        .into_iter()
+        .filter(|key| key.len() == 64 && key.ends_with(code_id.as_ref()))
+        .filter_map(|key| sp_io::storage::get(&key).map(|value| (key, value.to_vec())))
+        .collect()
+}
+
+fn save_code(code_id: CodeId) {
+    let entries = code_storage_entries(code_id);
+
+    if entries.is_empty() {
+        return;
+    }
+
+    code_cache()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(code_id, CachedCode { entries, hits: 0 });
+}
+
+// Copies the cached code into the current storage, returns false if the code is not cached
+fn load_code(code_id: CodeId) -> bool {
+    let mut cache = code_cache().lock().unwrap_or_else(PoisonError::into_inner);
+
+    let Some(code) = cache.get_mut(&code_id) else {
+        return false;
+    };
+
+    for (key, value) in code.entries.iter() {
+        sp_io::storage::set(key, value);
+    }
+
+    code.hits += 1;
+
+    true
+}
+
+/// Uploads a program, using the code cache if it is enabled
+pub(crate) fn upload_program(
+    signer: AccountId,
+    wasm: Vec<u8>,
+    salt: Vec<u8>,
+    payload: Vec<u8>,
+    gas_limit: u64,
+    value: u128,
+    keep_alive: bool,
+) -> DispatchResultWithPostInfo {
+    if !code_cache_enabled() {
+        return Gear::upload_program(
+            RuntimeOrigin::signed(signer),
+            wasm,
+            salt,
+            payload,
+            gas_limit,
+            value,
+            keep_alive,
+        );
+    }
+
+    let code_id = CodeId::generate(&wasm);
+
+    if load_code(code_id) {
+        return Gear::create_program(
+            RuntimeOrigin::signed(signer),
+            code_id,
+            salt,
+            payload,
+            gas_limit,
+            value,
+            keep_alive,
+        );
+    }
+
+    let result = Gear::upload_program(
+        RuntimeOrigin::signed(signer),
+        wasm,
+        salt,
+        payload,
+        gas_limit,
+        value,
+        keep_alive,
+    );
+
+    if result.is_ok() {
+        save_code(code_id);
+    }
+
+    result
+}
+
+/// Uploads a code, using the code cache if it is enabled
+pub(crate) fn upload_code(signer: AccountId, wasm: Vec<u8>) -> DispatchResultWithPostInfo {
+    if !code_cache_enabled() {
+        return Gear::upload_code(RuntimeOrigin::signed(signer), wasm);
+    }
+
+    let code_id = CodeId::generate(&wasm);
+
+    // If the code already exists, gear returns the error
+    if code_storage_entries(code_id).is_empty() && load_code(code_id) {
+        return Ok(().into());
+    }
+
+    let result = Gear::upload_code(RuntimeOrigin::signed(signer), wasm);
+
+    if result.is_ok() {
+        save_code(code_id);
+    }
+
+    result
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use common::GasTree;
+use frame_support::storage::storage_prefix;
+use gear_core::tasks::VaraScheduledTask;
+use parity_scale_codec::Decode;
+
+use crate::mock::{Balances, GearBank, System, Test};
+use crate::runtime_types::GEAR_INVARIANT_CHECKS;
+use crate::types::{
+    AccountId, Balance, GasHandlerOf, GasTreeOf, GearInvariantsReport, LeakedGasNodes, QueueOf,
+};
+use crate::utils::storage_keys_with_prefix;
+
+type GasNodeIdOf = <GasHandlerOf<Test> as GasTree>::NodeId;
+
+/// ## Enable the Gear invariants checks
+/// When enabled, the Gear invariants are checked after each block (`check_gear_invariants`),
+/// the test fails with the report if they are not met.
+pub fn set_gear_invariant_checks(enabled: bool) {
+    GEAR_INVARIANT_CHECKS.with(|value| *value.borrow_mut() = enabled)
+}
+
+pub fn gear_invariant_checks_enabled() -> bool {
+    GEAR_INVARIANT_CHECKS.with(|value| *value.borrow())
+}
+
+/// ## Check the Gear invariants
+/// Checks that the `GearBank` account holds at least the sum of the gas and value deposits, and
+/// that there are no gas nodes left in gas trees that are not used by a dispatch in the queue or
+/// a scheduled task (mailbox, waitlist, delayed dispatch, gas reservation, etc), grouped by the
+/// origin that paid for the gas.
+pub fn check_gear_invariants() -> Result<GearInvariantsReport, GearInvariantsReport> {
+    let (gas_deposits, value_deposits) =
+        storage_keys_with_prefix(&storage_prefix(b"GearBank", b"Bank"))
+            .into_iter()
+            .filter_map(|key| sp_io::storage::get(&key))
+            .filter_map(|value| <(Balance, Balance)>::decode(&mut &value[..]).ok())
+            .fold((0, 0), |(gas, value), (account_gas, account_value)| {
+                (
+                    gas.saturating_add(account_gas),
+                    value.saturating_add(account_value),
+                )
+            });
+
+    let unused_value = sp_io::storage::get(&storage_prefix(b"GearBank", b"UnusedValue"))
+        .and_then(|value| Balance::decode(&mut &value[..]).ok())
+        .unwrap_or_default();
+
+    let report = GearInvariantsReport {
+        block: System::block_number(),
+        bank_balance: Balances::free_balance(GearBank::bank_address()),
+        gas_deposits,
+        value_deposits,
+        unused_value,
+        leaked_gas_nodes: leaked_gas_nodes(),
+    };
+
+    if report.is_ok() {
+        Ok(report)
+    } else {
+        Err(report)
+    }
+}
+
+/// ## Assert the Gear invariants
+/// Fails the test with a readable report if the Gear invariants are not met.
+pub fn assert_gear_invariants() {
+    if let Err(report) = check_gear_invariants() {
+        panic!("{report}");
+    }
+}
+
+// Roots of the gas trees used by the dispatches in the queue and the scheduled tasks
+fn used_gas_roots() -> Vec<GasNodeIdOf> {
+    let task_pool_prefix = storage_prefix(b"GearScheduler", b"TaskPool");
+
+    // Task pool keys: prefix ++ block number (identity) ++ task (identity)
+    let task_nodes = storage_keys_with_prefix(&task_pool_prefix)
+        .into_iter()
+        .filter_map(|key| {
+            VaraScheduledTask::<AccountId>::decode(&mut &key[task_pool_prefix.len() + 8..]).ok()
+        })
+        .filter_map(|task| -> Option<GasNodeIdOf> {
+            match task {
+                VaraScheduledTask::<AccountId>::RemoveFromMailbox(_, message_id)
+                | VaraScheduledTask::<AccountId>::RemoveFromWaitlist(_, message_id)
+                | VaraScheduledTask::<AccountId>::WakeMessage(_, message_id)
+                | VaraScheduledTask::<AccountId>::SendDispatch(message_id)
+                | VaraScheduledTask::<AccountId>::SendUserMessage { message_id, .. } => {
+                    Some(message_id.into())
+                }
+                VaraScheduledTask::<AccountId>::RemoveGasReservation(_, reservation_id) => {
+                    Some(reservation_id.into())
+                }
+                _ => None,
+            }
+        });
+
+    QueueOf::<Test>::iter_keys()
+        .map(GasNodeIdOf::from)
+        .chain(task_nodes)
+        .filter_map(|node_id| <GasHandlerOf<Test> as GasTree>::get_origin_key(node_id).ok())
+        .collect()
+}
+
+// Gas nodes can only be leaked if nothing else will use them: the nodes of the gas trees without
+// a dispatch in the queue or a scheduled task
+fn leaked_gas_nodes() -> Vec<LeakedGasNodes> {
+    let used_roots = used_gas_roots();
+    let mut leaked: BTreeMap<AccountId, LeakedGasNodes> = BTreeMap::new();
+
+    for node_id in GasTreeOf::<Test>::iter_keys() {
+        let used = <GasHandlerOf<Test> as GasTree>::get_origin_key(node_id)
+            .is_ok_and(|root| used_roots.contains(&root));
+
+        if used {
+            continue;
+        }
+
+        let Ok(origin) = <GasHandlerOf<Test> as GasTree>::get_external(node_id) else {
+            continue;
+        };
+
+        let gas = <GasHandlerOf<Test> as GasTree>::get_limit(node_id).unwrap_or_default();
+
+        let entry = leaked.entry(origin.clone()).or_insert(LeakedGasNodes {
+            origin,
+            nodes: 0,
+            gas: 0,
+        });
+
+        entry.nodes += 1;
+        entry.gas = entry.gas.saturating_add(gas);
+    }
+
+    leaked.into_values().collect()
+}
@@ -1,8 +1,9 @@
 use common::{storage::Limiter, GasTree, MessageId, Origin};
 use frame_support::{
     assert_ok,
-    pallet_prelude::{DispatchClass, Weight},
-    traits::{EstimateNextSessionRotation, Get, OnFinalize, OnInitialize},
+    pallet_prelude::{DispatchClass, DispatchError, Weight},
+    storage::storage_prefix,
+    traits::{EstimateNextSessionRotation, FindAuthor, Get, OnFinalize, OnInitialize, Randomness},
 };
 use frame_system::{limits::BlockWeights, pallet_prelude::BlockNumberFor};
 use gear_core::ids::{
@@ -13,23 +14,75 @@ use gear_core_errors::{ErrorReplyReason, ReplyCode, SimpleExecutionError};
 use gprimitives::ActorId;
 use pallet_gear::Event as GearEvent;
 use pallet_gear_builtin::GasAllowanceOf;
+use sp_core::H256;
 use sp_runtime::testing::UintAuthorityId;
 
 use crate::ext_builder::ExtBuilder;
 use crate::mock::{
-    Authorship, Balances, Gear, GearBank, GearBuiltin, GearGas, GearMessenger, GearProgram,
-    GearScheduler, Historical, Offset, Period, Proxy, RuntimeEvent, RuntimeOrigin, Session,
-    Staking, System, Test, Timestamp,
+    Authorship, Balances, BlockGasLimit, BlockTime, EraPayoutValue, Gear, GearBank, GearBuiltin,
+    GearGas, GearMessenger, GearProgram, GearScheduler, Historical, Offset, Period, Proxy,
+    RuntimeEvent, Session, Staking, System, Test, Timestamp,
 };
 use crate::runtime_types::*;
 use crate::types::{
-    AccountId, Balance, BlockWeightsOf, GasHandlerOf, GasTreeOf, QueueOf, StakingEventType,
-    DEFAULT_GAS_LIMIT, ENDOWMENT,
+    AccountId, Balance, BlockExecutionInfo, BlockWeightsOf, GasHandlerOf, GasTreeOf,
+    QueueNotProcessedReport, QueueOf, StakingEventType, DEFAULT_GAS_LIMIT, ENDOWMENT,
 };
 
 use crate::contract::Contract;
-
-use parity_scale_codec::Encode;
+use crate::utils::storage_keys_with_prefix;
+pub use crate::utils::{account_label, account_name};
+
+use parity_scale_codec::{Decode, Encode};
+
+mod accounts;
+mod balance_tracker;
+mod balances;
+mod block_hooks;
+mod chain_state;
+mod code_cache;
+mod invariants;
+mod rewards;
+mod snapshot;
+#[cfg(feature = "try-runtime")]
+mod try_state;
+use accounts::with_account_names;
+pub use accounts::{account, accounts, clear_account_names, validators, TestAccount};
+pub use balance_tracker::BalanceTracker;
+pub use balances::{
+    freeze, freezes, lock, locks, remove_lock, reserve, reserved_balance, set_balance, set_lock,
+    slash, thaw, transfer, unreserve,
+};
+pub use block_hooks::{
+    add_after_block_hook, add_before_block_hook, clear_block_hooks, remove_block_hook,
+};
+use block_hooks::{
+    add_events_reset_hook, mark_block_start, reset_block_start, run_after_block_hooks,
+    run_before_block_hooks, run_events_reset_hooks,
+};
+pub use chain_state::ChainState;
+pub use code_cache::{
+    clear_code_cache, code_cache_enabled, code_cache_hits, code_cached, set_code_cache,
+};
+pub(crate) use code_cache::{upload_code, upload_program};
+pub use invariants::{
+    assert_gear_invariants, check_gear_invariants, gear_invariant_checks_enabled,
+    set_gear_invariant_checks,
+};
+use rewards::payout_ended_eras;
+pub use rewards::{
+    author_rotation_enabled, auto_payout_enabled, era_reward_points, era_total_stake,
+    era_validator_reward, payout_era, rewards_pool, rewards_pool_balance, set_author_rotation,
+    set_auto_payout, vara_era_payout, vara_rewards,
+};
+pub use snapshot::StateSnapshot;
+#[cfg(feature = "try-runtime")]
+pub use try_state::{
+    assert_try_state, set_try_state_checks, try_state, try_state_checked_blocks,
+    try_state_checks_enabled, TRY_STATE_PALLETS,
+};
+#[cfg(feature = "try-runtime")]
+use try_state::{check_try_state_after_block, reset_try_state_checks};
 
 /// Account (u64) to actorId
 pub fn u64_to_actorid(account: u64) -> ActorId {
@@ -41,9 +94,49 @@ pub fn u64_to_origin_u64(account: u64) -> u64 {
     u64::from_origin(account.into_origin())
 }
 
+/// Account to the ActorId that the contracts see (`msg::source()`)
+pub fn account_to_actor_id(account: AccountId) -> ActorId {
+    ActorId::from_origin(account.into_origin())
+}
+
 /// Bloque duration in ms
 pub fn block_in_ms() -> u64 {
-    <Test as pallet_timestamp::Config>::MinimumPeriod::get().saturating_mul(2)
+    BlockTime::get()
+}
+
+/// ## Set the block duration in ms
+/// The timestamp of each new block will be incremented by this value (3_000 ms by default)
+pub fn set_block_time(block_time_ms: u64) {
+    BlockTime::set(block_time_ms);
+}
+
+/// ## Set the current timestamp
+/// Set the timestamp in ms of the current block, next blocks will continue from this value
+pub fn set_timestamp(timestamp_ms: u64) {
+    Timestamp::set_timestamp(timestamp_ms);
+}
+
+/// ## Advance time
+/// Jump the timestamp of the current block forward by the given ms without changing the block number
+pub fn advance_time(ms: u64) {
+    set_timestamp(current_timestamp().saturating_add(ms));
+}
+
+/// ## Freeze time
+/// The timestamp will not change when new blocks are run until `unfreeze_time` is called, the time
+/// can still be changed with `advance_time` and `set_timestamp`
+pub fn freeze_time() {
+    TIME_FROZEN.with(|value| *value.borrow_mut() = true)
+}
+
+/// ## Unfreeze time
+/// New blocks will increment the timestamp again by the block duration
+pub fn unfreeze_time() {
+    TIME_FROZEN.with(|value| *value.borrow_mut() = false)
+}
+
+pub fn time_frozen() -> bool {
+    TIME_FROZEN.with(|value| *value.borrow())
 }
 
 /// Session duration in blocks
@@ -104,18 +197,102 @@ pub fn next_session_rotation_block() -> Option<u64> {
     block
 }
 
+/// Bonding duration in eras
+pub fn bonding_duration() -> u32 {
+    <Test as pallet_staking::Config>::BondingDuration::get()
+}
+
+/// Author of the current block (receives the fees of the burned gas and the reward points)
+pub fn block_author() -> Option<AccountId> {
+    <Test as pallet_authorship::Config>::FindAuthor::find_author(
+        System::digest()
+            .logs()
+            .iter()
+            .filter_map(|item| item.as_pre_runtime()),
+    )
+}
+
+/// Rewards paid to the validators at the end of each era (not used with the Vara rewards model)
+pub fn era_payout() -> Balance {
+    EraPayoutValue::get()
+}
+
+/// Total issuance of the tokens
+pub fn total_issuance() -> Balance {
+    pallet_balances::TotalIssuance::<Test>::get()
+}
+
+/// Minimum balance that an account needs to exist
+pub fn existential_deposit() -> Balance {
+    <Test as pallet_balances::Config>::ExistentialDeposit::get()
+}
+
+/// Value of each unit of gas
+pub fn gas_to_value(gas: u64) -> Balance {
+    <Test as pallet_gear_bank::Config>::GasMultiplier::get().gas_to_value(gas)
+}
+
 /// ## Function to go to the next finished bonding duration
 /// Use this function if you want to rapidly pass the bonding duration, if you use it, features like rewards
-/// will not work correctly or with expected results
+/// will not work correctly or with expected results.
+///
+/// To pass the bonding duration with the real session and era rotation use: `advance_eras(bonding_duration())`
 pub fn move_n_bonding_durations(bonding_durations: u32) {
     pallet_staking::CurrentEra::<Test>::put(
         <Test as pallet_staking::Config>::BondingDuration::get() + bonding_durations,
     );
 }
 
+/// ## Advance to the next session
+/// Runs the blocks until the next session rotation (given by `next_session_rotation_block`), sessions are
+/// rotated by the session and staking hooks, so the staking state is the same as in a real chain.
+///
+/// Blocks without messages in the queue and without scheduled tasks skip the Gear execution.
+pub fn advance_to_next_session() {
+    let session_index = current_session_index();
+    let rotation_block =
+        next_session_rotation_block().expect("Next session rotation can't be estimated");
+
+    fast_forward_blocks(rotation_block.saturating_sub(current_block()));
+
+    assert!(
+        current_session_index() > session_index,
+        "Session {session_index} was not rotated at block {rotation_block}"
+    );
+}
+
+/// ## Advance to the next era
+/// Runs sessions (with `advance_to_next_session`) until the active era changes, at most it will run
+/// `sessions_per_era` + 1 sessions (the genesis era lasts one session less).
+///
+/// > Note: the eras only change if the runtime was created with authorities and sessions
+/// (new_test_ext_with_authorities_and_sessions)
+pub fn advance_to_next_era() {
+    let era_index = current_era_index();
+
+    for _ in 0..=sessions_per_era() {
+        advance_to_next_session();
+
+        if current_era_index() > era_index {
+            return;
+        }
+    }
+
+    panic!("Era {era_index} did not end after {} sessions", sessions_per_era() + 1);
+}
+
+/// ## Advance n eras
+/// Runs `advance_to_next_era` n times, use it to pass the bonding duration (`advance_eras(bonding_duration())`)
+/// keeping rewards and unlocking chunks with the correct state.
+pub fn advance_eras(eras: u32) {
+    for _ in 0..eras {
+        advance_to_next_era();
+    }
+}
+
 /// ## Return contract nominations
-pub fn contract_nominators(contract: &Contract) -> Vec<u64> {
-    let targets_before = pallet_staking::Nominators::<Test>::get(contract.account)
+pub fn contract_nominators(contract: &Contract) -> Vec<AccountId> {
+    let targets_before = pallet_staking::Nominators::<Test>::get(&contract.account)
         .map_or_else(Vec::new, |x| x.targets.into_inner());
 
     targets_before
@@ -127,36 +304,352 @@ pub fn run_to_next_block() {
 }
 
 /// ## Run to next n blocks
+/// If the fast forward mode is enabled (`set_fast_forward(true)`) and no remaining weight is given,
+/// the idle blocks will skip the Gear execution (see `fast_forward_blocks`).
 pub fn run_for_n_blocks(n: u64, remaining_weight: Option<u64>) {
+    if remaining_weight.is_none() && fast_forward_enabled() {
+        fast_forward_blocks(n);
+        return;
+    }
+
     let now = System::block_number();
     let until = now + n;
     for current_blk in now..until {
-        if let Some(remaining_weight) = remaining_weight {
-            GasAllowanceOf::<Test>::put(remaining_weight);
-            let max_block_weight = <BlockWeightsOf<Test> as Get<BlockWeights>>::get().max_block;
-            System::register_extra_weight_unchecked(
-                max_block_weight.saturating_sub(Weight::from_parts(remaining_weight, 0)),
-                DispatchClass::Normal,
-            );
+        run_block(current_blk, remaining_weight);
+    }
+}
+
+/// ## Run until a condition is met
+/// Runs block by block until `condition` returns true, returning the block where it stopped. If the
+/// condition is not met after `max_blocks` blocks, it returns None.
+///
+/// The condition is checked before running the first block.
+pub fn run_until(mut condition: impl FnMut() -> bool, max_blocks: u64) -> Option<u64> {
+    for _ in 0..max_blocks {
+        if condition() {
+            return Some(current_block());
         }
 
-        let max_block_weight = <BlockWeightsOf<Test> as Get<BlockWeights>>::get().max_block;
-        System::register_extra_weight_unchecked(max_block_weight, DispatchClass::Mandatory);
-        Gear::run(frame_support::dispatch::RawOrigin::None.into(), None).unwrap();
+        run_to_next_block();
+    }
+
+    condition().then(current_block)
+}
+
+/// ## Run until Gear is idle
+/// Runs block by block until the message queue is empty and there are no due tasks in the scheduler
+/// (waitlist wake ups, delayed messages, etc.), returning the block where it stopped. If Gear is not
+/// idle after `max_blocks` blocks, it returns None.
+pub fn run_until_idle(max_blocks: u64) -> Option<u64> {
+    run_until(gear_idle, max_blocks)
+}
+
+/// ## Gear is idle
+/// Returns true if the message queue is empty and there are no due tasks in the scheduler
+pub fn gear_idle() -> bool {
+    gear_idle_at(gear_block_number())
+}
+
+/// ## Enable or disable the fast forward mode
+/// With the fast forward mode enabled, `run_for_n_blocks` (and the functions that use it) will run
+/// idle blocks as `fast_forward_blocks` does.
+pub fn set_fast_forward(enabled: bool) {
+    FAST_FORWARD.with(|value| *value.borrow_mut() = enabled)
+}
+
+pub fn fast_forward_enabled() -> bool {
+    FAST_FORWARD.with(|value| *value.borrow())
+}
+
+/// ## Fast forward n blocks
+/// Runs n blocks, blocks where the message queue is empty and there are no due tasks in the scheduler
+/// only execute the hooks that handle the timestamp and the session and era rotation, skipping the
+/// Gear execution, so advancing a full era (14_400 blocks) takes a fraction of the time.
+///
+/// Blocks with messages or due tasks, and the blocks that skip the Gear run (`skip_gear_run`), are
+/// executed as in `run_for_n_blocks`. Idle blocks are recorded as the last block info (with the
+/// gas allowance unused and no dispatches left), so the result doesn't depend on the idle blocks.
+pub fn fast_forward_blocks(n: u64) {
+    let now = System::block_number();
+    let until = now + n;
+    for current_blk in now..until {
+        if gear_run_skipped() || !gear_idle_at(gear_block_number()) {
+            run_block(current_blk, None);
+            continue;
+        }
+
+        run_before_block_hooks(current_blk);
+        on_finalize_idle(current_blk);
 
-        on_finalize(current_blk);
+        let block_info = BlockExecutionInfo {
+            block: current_blk,
+            gas_allowance_left: gas_allowance().unwrap_or_else(GasAllowanceOf::<Test>::get),
+            dispatches_left: 0,
+        };
+
+        LAST_BLOCK_INFO.with(|info| *info.borrow_mut() = Some(block_info));
+
+        if gear_invariant_checks_enabled() {
+            assert_gear_invariants();
+        }
+
+        #[cfg(feature = "try-runtime")]
+        check_try_state_after_block();
+
+        run_after_block_hooks(current_blk);
 
         let new_block_number = current_blk + 1;
         System::set_block_number(new_block_number);
-        on_initialize(new_block_number);
+
+        // The last block is fully initialized, so messages can be sent after the fast forward
+        if new_block_number < until && !gear_run_skipped() && gear_idle_at(gear_block_number() + 1)
+        {
+            on_initialize_idle(new_block_number);
+        } else {
+            on_initialize(new_block_number);
+        }
     }
 }
 
+// Execute the Gear queue and all the pallets hooks for the given block
+fn run_block(current_blk: BlockNumberFor<Test>, remaining_weight: Option<u64>) {
+    run_before_block_hooks(current_blk);
+
+    let first_block_event = System::event_count() as usize;
+
+    if let Some(remaining_weight) = remaining_weight.or_else(gas_allowance) {
+        GasAllowanceOf::<Test>::put(remaining_weight);
+        let max_block_weight = <BlockWeightsOf<Test> as Get<BlockWeights>>::get().max_block;
+        System::register_extra_weight_unchecked(
+            max_block_weight.saturating_sub(Weight::from_parts(remaining_weight, 0)),
+            DispatchClass::Normal,
+        );
+    }
+
+    let max_block_weight = <BlockWeightsOf<Test> as Get<BlockWeights>>::get().max_block;
+    System::register_extra_weight_unchecked(max_block_weight, DispatchClass::Mandatory);
+    let run_error = if take_skipped_gear_run() {
+        None
+    } else {
+        Gear::run(frame_support::dispatch::RawOrigin::None.into(), None)
+            .err()
+            .map(|error| error.error)
+    };
+
+    let block_info = BlockExecutionInfo {
+        block: current_blk,
+        gas_allowance_left: GasAllowanceOf::<Test>::get(),
+        dispatches_left: message_queue_len(),
+    };
+
+    LAST_BLOCK_INFO.with(|info| *info.borrow_mut() = Some(block_info));
+
+    on_finalize(current_blk);
+    check_queue_processed(current_blk, first_block_event, run_error);
+
+    if gear_invariant_checks_enabled() {
+        assert_gear_invariants();
+    }
+
+    #[cfg(feature = "try-runtime")]
+    check_try_state_after_block();
+
+    run_after_block_hooks(current_blk);
+
+    let new_block_number = current_blk + 1;
+    System::set_block_number(new_block_number);
+    on_initialize(new_block_number);
+}
+
+// Check the `QueueNotProcessed` events of the block and the `Gear::run` error, by default the test
+// fails if the queue was not processed, if it is allowed, the block will be recorded in the queue
+// not processed reports (with the error).
+fn check_queue_processed(
+    current_blk: BlockNumberFor<Test>,
+    first_block_event: usize,
+    run_error: Option<DispatchError>,
+) {
+    let queue_not_processed = System::read_events_no_consensus()
+        .skip(first_block_event)
+        .any(|e| {
+            matches!(
+                e.event,
+                RuntimeEvent::Gear(pallet_gear::Event::QueueNotProcessed)
+            )
+        });
+
+    if !queue_not_processed && run_error.is_none() {
+        return;
+    }
+
+    let dispatches_left = message_queue_len();
+
+    assert!(
+        queue_not_processed_allowed(),
+        "Gear queue was not processed in block {current_blk} ({dispatches_left} dispatches left in the queue, error: {run_error:?})"
+    );
+
+    QUEUE_NOT_PROCESSED_REPORTS.with(|reports| {
+        reports.borrow_mut().push(QueueNotProcessedReport {
+            block: current_blk,
+            dispatches_left,
+            error: run_error,
+        })
+    });
+}
+
+/// ## Skip the Gear run
+/// The next `blocks` blocks will not run the Gear queue (as if the block producer did not include
+/// the Gear inherent), so the pallet emits `QueueNotProcessed`. Use it with
+/// `allow_queue_not_processed(true)` to test how contracts handle blocks without execution.
+pub fn skip_gear_run(blocks: u32) {
+    SKIPPED_GEAR_RUNS.with(|value| *value.borrow_mut() = blocks)
+}
+
+// Returns true if the Gear run of the next block must be skipped
+fn gear_run_skipped() -> bool {
+    SKIPPED_GEAR_RUNS.with(|value| *value.borrow() > 0)
+}
+
+// Returns true (and counts the block) if the Gear run of this block must be skipped
+fn take_skipped_gear_run() -> bool {
+    SKIPPED_GEAR_RUNS.with(|value| {
+        let mut skipped = value.borrow_mut();
+
+        if *skipped == 0 {
+            return false;
+        }
+
+        *skipped -= 1;
+        true
+    })
+}
+
+/// ## Allow queue not processed
+/// By default, the test fails if the Gear queue is not processed in a block (`QueueNotProcessed`
+/// event), if allowed, the blocks will be recorded with the number of dispatches left in the queue
+/// (`queue_not_processed_reports`), so stress tests can check that the pending messages are
+/// processed in later blocks.
+pub fn allow_queue_not_processed(allowed: bool) {
+    QUEUE_NOT_PROCESSED_ALLOWED.with(|value| *value.borrow_mut() = allowed)
+}
+
+pub fn queue_not_processed_allowed() -> bool {
+    QUEUE_NOT_PROCESSED_ALLOWED.with(|value| *value.borrow())
+}
+
+/// ## Queue not processed reports
+/// Blocks where the Gear queue was not processed (only recorded with `allow_queue_not_processed(true)`)
+pub fn queue_not_processed_reports() -> Vec<QueueNotProcessedReport> {
+    QUEUE_NOT_PROCESSED_REPORTS.with(|reports| reports.borrow().clone())
+}
+
+pub fn clear_queue_not_processed_reports() {
+    QUEUE_NOT_PROCESSED_REPORTS.with(|reports| reports.borrow_mut().clear())
+}
+
+/// ## Set the block gas limit
+/// Gas limit of each block (100_000_000_000_000 by default), it is the gas allowance that the Gear
+/// execution has at the beginning of each block. It will be used from the next block.
+pub fn set_block_gas_limit(gas: u64) {
+    BlockGasLimit::set(gas);
+}
+
+pub fn block_gas_limit() -> u64 {
+    BlockGasLimit::get()
+}
+
+/// ## Set the gas allowance per block
+/// Gas allowance that the Gear execution will have in each block (like the `remaining_weight` argument
+/// of `run_for_n_blocks`), dispatches that don't fit in the allowance are left in the queue for the next
+/// blocks. Set it to None to use the block gas limit again.
+pub fn set_gas_allowance(gas_allowance: Option<u64>) {
+    GAS_ALLOWANCE.with(|value| *value.borrow_mut() = gas_allowance)
+}
+
+pub fn gas_allowance() -> Option<u64> {
+    GAS_ALLOWANCE.with(|value| *value.borrow())
+}
+
+/// ## Last block info
+/// Returns the gas allowance left and the dispatches left in the queue after the Gear execution of
+/// the last block (the idle blocks of the fast forward leave the gas allowance unused)
+pub fn last_block_info() -> Option<BlockExecutionInfo> {
+    LAST_BLOCK_INFO.with(|info| *info.borrow())
+}
+
+/// ## Set the random seed
+/// The random output that contracts get (random syscalls) is derived from the seed, the block number
+/// and the subject, so the same seed always gives the same outputs. Set it to None to use the default
+/// randomness (the subject padded with zeros).
+pub fn set_random_seed(seed: Option<u64>) {
+    RANDOM_SEED.with(|value| *value.borrow_mut() = seed)
+}
+
+pub fn random_seed() -> Option<u64> {
+    RANDOM_SEED.with(|value| *value.borrow())
+}
+
+/// ## Set a fixed random output
+/// Random output returned in every block (it has priority over the seed), so a given outcome of
+/// a lottery or a game can be forced. Set it to None to remove it.
+pub fn set_random_output(output: Option<[u8; 32]>) {
+    RANDOM_OUTPUT.with(|value| *value.borrow_mut() = output)
+}
+
+/// ## Set the random output of a block
+/// Random output returned only in the given block, it has priority over the fixed output and the seed.
+pub fn set_random_output_at(block: u64, output: [u8; 32]) {
+    RANDOM_OUTPUTS_BY_BLOCK.with(|outputs| outputs.borrow_mut().insert(block, output));
+}
+
+/// Removes the seed and the random outputs, the default randomness is used again
+pub fn reset_randomness() {
+    set_random_seed(None);
+    set_random_output(None);
+    RANDOM_OUTPUTS_BY_BLOCK.with(|outputs| outputs.borrow_mut().clear());
+}
+
+/// ## Random output
+/// Returns the random output and the block number that the runtime gives for the subject in the
+/// current block. Keep in mind that the random syscall of the contracts uses its own subject
+/// (the message id), so the output only matches when it is fixed.
+pub fn random(subject: &[u8]) -> ([u8; 32], u64) {
+    let (output, block) =
+        <<Test as pallet_gear::Config>::Randomness as Randomness<H256, u64>>::random(subject);
+
+    (output.0, block)
+}
+
+/// Gear internal block number (used by the scheduler and the waitlist)
+pub fn gear_block_number() -> u64 {
+    <Gear as Get<BlockNumberFor<Test>>>::get()
+}
+
+/// ## Next scheduled task block
+/// Returns the lowest Gear block number that has a task in the scheduler (waitlist removals, delayed
+/// messages, reservations removals, etc.)
+pub fn next_scheduled_task_block() -> Option<u64> {
+    let task_pool_prefix = storage_prefix(b"GearScheduler", b"TaskPool");
+
+    // Task pool keys: prefix ++ block number (identity) ++ task (identity)
+    storage_keys_with_prefix(&task_pool_prefix)
+        .into_iter()
+        .filter_map(|key| u64::decode(&mut &key[task_pool_prefix.len()..]).ok())
+        .min()
+}
+
+// Gear has nothing to do at the given Gear block: empty message queue and no due tasks
+fn gear_idle_at(gear_block: u64) -> bool {
+    message_queue_empty()
+        && next_scheduled_task_block().is_none_or(|task_block| task_block > gear_block)
+}
+
 /// ## User balance
 /// Returns the user balance.
 ///
 /// To know the balance of a contract use: contract_free_balance
-pub fn balance_from_user(address: u64) -> u128 {
+pub fn balance_from_user(address: AccountId) -> u128 {
     Balances::free_balance(address)
 }
 
@@ -173,11 +666,11 @@ pub fn upload_sails_wasm<T: Encode>(
     gas_limit: Option<u64>,
 ) -> Contract {
     let contract_id = ActorId::generate_from_user(CodeId::generate(wasm), b"contract");
-    let contract_account_id = u64::from_origin(contract_id.into_origin());
+    let contract_account_id = AccountId::from_origin(contract_id.into_origin());
     let gas_limit = gas_limit.unwrap_or(DEFAULT_GAS_LIMIT);
 
-    assert_ok!(Gear::upload_program(
-        RuntimeOrigin::signed(signer),
+    assert_ok!(upload_program(
+        signer,
         wasm.to_vec(),
         salt.to_vec(),
         init_payload.encode(),
@@ -205,11 +698,11 @@ pub fn upload_wasm(
     gas_limit: Option<u64>,
 ) -> Contract {
     let contract_id = ActorId::generate_from_user(CodeId::generate(wasm), b"contract");
-    let contract_account_id = u64::from_origin(contract_id.into_origin());
+    let contract_account_id = AccountId::from_origin(contract_id.into_origin());
     let gas_limit = gas_limit.unwrap_or(DEFAULT_GAS_LIMIT);
 
-    assert_ok!(Gear::upload_program(
-        RuntimeOrigin::signed(signer),
+    assert_ok!(upload_program(
+        signer,
         wasm.to_vec(),
         salt.to_vec(),
         Default::default(),
@@ -227,11 +720,12 @@ pub fn upload_wasm(
 
 // Run on_initialize hooks in order as they appear in AllPalletsWithSystem.
 pub(crate) fn on_initialize(new_block_number: BlockNumberFor<Test>) {
+    mark_block_start();
+
     //
     System::on_initialize(new_block_number);
 
-    // Timestamp::set_timestamp(new_block_number.saturating_mul(MILLISECS_PER_BLOCK));
-    Timestamp::set_timestamp(new_block_number.saturating_mul(block_in_ms()));
+    update_timestamp();
 
     // Authorship::on_initialize(new_block_number);
     // GearGas::on_initialize(new_block_number);
@@ -251,6 +745,41 @@ pub(crate) fn on_initialize(new_block_number: BlockNumberFor<Test>) {
     Gear::on_initialize(new_block_number);
     GearGas::on_initialize(new_block_number);
     GearBuiltin::on_initialize(new_block_number);
+
+    payout_ended_eras();
+}
+
+// Increment the timestamp by the block duration (unless time is frozen)
+fn update_timestamp() {
+    if time_frozen() {
+        return;
+    }
+
+    Timestamp::set_timestamp(current_timestamp().saturating_add(block_in_ms()));
+}
+
+// Run on_initialize hooks of the pallets that handle time, sessions and eras. Gear only increments
+// its block number so the scheduled tasks keep the same timing.
+fn on_initialize_idle(new_block_number: BlockNumberFor<Test>) {
+    mark_block_start();
+    System::on_initialize(new_block_number);
+    update_timestamp();
+    Authorship::on_initialize(new_block_number);
+    Session::on_initialize(new_block_number);
+    Historical::on_initialize(new_block_number);
+    Staking::on_initialize(new_block_number);
+    Gear::on_initialize(new_block_number);
+
+    payout_ended_eras();
+}
+
+// Run on_finalize hooks of the pallets that handle time, sessions and eras (Gear was not executed,
+// so its hooks are skipped).
+fn on_finalize_idle(current_blk: BlockNumberFor<Test>) {
+    Staking::on_finalize(current_blk);
+    Historical::on_finalize(current_blk);
+    Session::on_finalize(current_blk);
+    Authorship::on_finalize(current_blk);
 }
 
 // Run on_finalize hooks (in pallets reverse order, as they appear in AllPalletsWithSystem)
@@ -271,13 +800,28 @@ pub(crate) fn on_finalize(current_blk: BlockNumberFor<Test>) {
     Historical::on_finalize(current_blk);
     Session::on_finalize(current_blk);
     Authorship::on_finalize(current_blk);
+}
 
-    assert!(!System::events().iter().any(|e| {
-        matches!(
-            e.event,
-            RuntimeEvent::Gear(pallet_gear::Event::QueueNotProcessed)
-        )
-    }))
+// Reset the runtime settings that are kept by thread (each test externalities starts with the
+// default settings, even if tests are executed in the same thread).
+pub(crate) fn reset_runtime_state() {
+    set_fast_forward(false);
+    unfreeze_time();
+    set_gas_allowance(None);
+    LAST_BLOCK_INFO.with(|info| *info.borrow_mut() = None);
+    allow_queue_not_processed(false);
+    skip_gear_run(0);
+    clear_queue_not_processed_reports();
+    TRANSACTION_DEPTH.with(|depth| *depth.borrow_mut() = 0);
+    DEBUG_EXECUTION_TRACE.with(|stack| stack.borrow_mut().clear());
+    set_code_cache(false);
+    set_gear_invariant_checks(false);
+    reset_randomness();
+    set_author_rotation(false);
+    set_auto_payout(false);
+    #[cfg(feature = "try-runtime")]
+    reset_try_state_checks();
+    clear_block_hooks();
 }
 
 /// ## Init logger
@@ -285,33 +829,73 @@ pub fn init_logger() {
     let _ = tracing_subscriber::fmt::try_init();
 }
 
+/// ## Events dump
+/// Returns the events of the runtime (one per line), the ids of the named accounts are replaced
+/// by their names (`account("alice")`), so the events are easier to read in the test logs.
+pub fn events_dump() -> String {
+    System::read_events_no_consensus()
+        .map(|record| with_account_names(&format!("{:?}", record.event)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Prints the events of the runtime with the names of the accounts (`events_dump`)
+pub fn print_events() {
+    println!("{}", events_dump());
+}
+
 /// ## Reset system events
+/// The balance trackers read the events emitted since their last read before the events are reset.
 pub fn reset_system_events() {
+    run_events_reset_hooks();
     System::reset_events();
+    reset_block_start();
 }
 
 pub fn current_stack() -> Vec<ExecutionTraceFrame> {
     DEBUG_EXECUTION_TRACE.with(|stack| stack.borrow().clone())
 }
 
+/// Returns true if there is at least one storage transaction open
 pub fn in_transaction() -> bool {
-    IN_TRANSACTION.with(|value| *value.borrow())
+    transaction_depth() > 0
 }
 
+/// Number of nested storage transactions open
+pub fn transaction_depth() -> u32 {
+    TRANSACTION_DEPTH.with(|depth| *depth.borrow())
+}
+
+/// Sets the transaction flag (`in_transaction`) without opening or closing a transaction
+#[deprecated(note = "use `start_transaction`, `commit_transaction` or `rollback_transaction`")]
 pub fn set_transaction_flag(new_val: bool) {
-    IN_TRANSACTION.with(|value| *value.borrow_mut() = new_val)
+    TRANSACTION_DEPTH.with(|depth| {
+        let mut depth = depth.borrow_mut();
+
+        if !new_val {
+            *depth = 0;
+        } else if *depth == 0 {
+            *depth = 1;
+        }
+    })
 }
 
 pub fn message_queue_empty() -> bool {
     QueueOf::<Test>::iter_keys().next().is_none()
 }
 
+/// Number of dispatches in the message queue
+pub fn message_queue_len() -> usize {
+    QueueOf::<Test>::iter_keys().count()
+}
+
 pub fn gas_tree_empty() -> bool {
     GasTreeOf::<Test>::iter_keys().next().is_none()
         && <GasHandlerOf<Test> as GasTree>::total_supply() == 0
 }
 
-pub fn message_id_fom_message_sent(signer: u64, contract_id: ActorId) -> Option<MessageId> {
+pub fn message_id_fom_message_sent(signer: AccountId, contract_id: ActorId) -> Option<MessageId> {
+    let signer = account_to_actor_id(signer);
     let mut msg_id = None;
 
     for ev in System::events() {
@@ -393,61 +977,152 @@ pub fn assert_payload_contains(s: &'static str) {
     }))
 }
 
+/// ## Start a storage transaction
+/// Transactions can be nested, each one needs to be closed with `commit_transaction` or
+/// `rollback_transaction`, prefer `with_rollback` or `with_transaction` that close it for you.
 pub fn start_transaction() {
     sp_externalities::with_externalities(|ext| ext.storage_start_transaction())
         .expect("externalities should exists");
 
-    set_transaction_flag(true);
+    TRANSACTION_DEPTH.with(|depth| *depth.borrow_mut() += 1);
 }
 
+/// ## Discard the changes of the last storage transaction
 pub fn rollback_transaction() {
-    sp_externalities::with_externalities(|ext| {
-        ext.storage_rollback_transaction()
-            .expect("ongoing transaction must be there");
+    assert!(
+        close_transaction(false),
+        "ongoing transaction must be there"
+    );
+}
+
+/// ## Keep the changes of the last storage transaction
+/// Changes are added to the parent transaction (if any).
+pub fn commit_transaction() {
+    assert!(close_transaction(true), "ongoing transaction must be there");
+}
+
+// It does not panic, so it can be used while unwinding a panic
+fn close_transaction(commit: bool) -> bool {
+    let closed = sp_externalities::with_externalities(|ext| {
+        if commit {
+            ext.storage_commit_transaction().is_ok()
+        } else {
+            ext.storage_rollback_transaction().is_ok()
+        }
     })
-    .expect("externalities should be set");
+    .unwrap_or(false);
+
+    if closed {
+        TRANSACTION_DEPTH.with(|depth| {
+            let mut depth = depth.borrow_mut();
+            *depth = depth.saturating_sub(1);
+        });
+    }
 
-    set_transaction_flag(false);
+    closed
 }
 
-/// ## Create a new runtime test
-/// This function will init the tests, you need to pass the address that will receive tokens (1000 tokens)
-pub fn new_test_ext(addresses_to_fund_tokens: Vec<u64>) -> sp_io::TestExternalities {
+/// Closes the transaction opened in a scope when it is dropped (also on panic),
+/// transactions opened inside the scope that were not closed are rolled back.
+struct TransactionGuard {
+    depth: u32,
+    commit: bool,
+}
+
+impl TransactionGuard {
+    fn start() -> Self {
+        let depth = transaction_depth();
+        start_transaction();
+
+        Self {
+            depth,
+            commit: false,
+        }
+    }
+}
+
+impl Drop for TransactionGuard {
+    fn drop(&mut self) {
+        while transaction_depth() > self.depth + 1 {
+            if !close_transaction(false) {
+                return;
+            }
+        }
+
+        if transaction_depth() > self.depth {
+            close_transaction(self.commit);
+        }
+    }
+}
+
+/// ## Run a closure and discard its changes
+/// All the storage changes made by the closure are rolled back, even if it panics, and its
+/// result is returned. It can be nested. Example: read the state after sending some messages
+/// without modifying the test.
+///
+/// > Note: only the storage is rolled back, runtime settings (block time, fast forward, etc)
+/// > changed inside the closure are kept.
+pub fn with_rollback<R>(execute: impl FnOnce() -> R) -> R {
+    let _guard = TransactionGuard::start();
+
+    execute()
+}
+
+/// ## Run a closure in a storage transaction
+/// Storage changes are kept if the closure returns `Ok`, and rolled back if it returns
+/// `Err` or panics. It can be nested.
+pub fn with_transaction<R, E>(execute: impl FnOnce() -> Result<R, E>) -> Result<R, E> {
+    let mut guard = TransactionGuard::start();
+
+    let result = execute();
+    guard.commit = result.is_ok();
+
+    result
+}
+
+/// ## Create a new runtime builder
+/// Returns an ExtBuilder with the given addresses, the gear bank and builtins funded (1000 tokens),
+/// use it to override runtime parameters (era length, existential deposit, etc) before build it.
+pub fn new_ext_builder(addresses_to_fund_tokens: Vec<AccountId>) -> ExtBuilder {
     let bank_address = GearBank::bank_address();
 
     let mut endowed_accounts = vec![bank_address];
 
-    endowed_accounts.extend(addresses_to_fund_tokens.iter());
+    endowed_accounts.extend(addresses_to_fund_tokens);
     endowed_accounts.extend(GearBuiltin::list_builtins());
 
     ExtBuilder::default()
         .endowment(ENDOWMENT)
         .with_endowed_accounts(endowed_accounts)
-        .build()
+}
+
+/// ## Create a new runtime test
+/// This function will init the tests, you need to pass the address that will receive tokens (1000 tokens)
+pub fn new_test_ext(addresses_to_fund_tokens: Vec<AccountId>) -> sp_io::TestExternalities {
+    new_ext_builder(addresses_to_fund_tokens).build()
+}
+
+/// ## Create a new runtime test with custom balances
+/// Each address receives its own balance instead of the 1000 tokens, with zero balance the account is not
+/// created, the balances must be zero or at least the existential deposit. Contract accounts can be
+/// funded before the contract is uploaded (`Contract::predict`).
+pub fn new_test_ext_with_balances(balances: Vec<(AccountId, Balance)>) -> sp_io::TestExternalities {
+    new_ext_builder(vec![]).with_balances(balances).build()
 }
 
 /// ## Create a new runtime test
 /// This function will init the tests, you need to pass the address that will receive tokens (1000 tokens)
 /// and initial authorities
 pub fn new_test_ext_with_authorities(
-    addresses_to_fund_tokens: Vec<u64>,
-    initial_authorities: Vec<u64>,
+    addresses_to_fund_tokens: Vec<AccountId>,
+    initial_authorities: Vec<AccountId>,
 ) -> sp_io::TestExternalities {
-    let bank_address = GearBank::bank_address();
-
-    let mut endowed_accounts = vec![bank_address];
-
-    endowed_accounts.extend(addresses_to_fund_tokens.iter());
-    endowed_accounts.extend(GearBuiltin::list_builtins());
-
     let initial_authorities = initial_authorities
         .into_iter()
         .map(|authority_address| (authority_address, None))
         .collect();
 
-    ExtBuilder::default()
-        .endowment(ENDOWMENT)
-        .with_endowed_accounts(endowed_accounts)
+    new_ext_builder(addresses_to_fund_tokens)
         .with_initial_authorities(initial_authorities)
         .build()
 }
@@ -476,16 +1151,9 @@ pub fn new_test_ext_with_authorities(
 /// This function will init the tests, you need to pass the address that will receive tokens (1000 tokens),
 /// and initial authorities, this function will enable session to manage rewards from staking, etc.
 pub fn new_test_ext_with_authorities_and_sessions(
-    addresses_to_fund_tokens: Vec<u64>,
-    initial_authorities: Vec<(u64, u64)>,
+    addresses_to_fund_tokens: Vec<AccountId>,
+    initial_authorities: Vec<(AccountId, u64)>,
 ) -> sp_io::TestExternalities {
-    let bank_address = GearBank::bank_address();
-
-    let mut endowed_accounts = vec![bank_address];
-
-    endowed_accounts.extend(addresses_to_fund_tokens.iter());
-    endowed_accounts.extend(GearBuiltin::list_builtins());
-
     let initial_authorities = initial_authorities
         .into_iter()
         .map(|(authority_address, authority_auth_id)| {
@@ -493,9 +1161,7 @@ pub fn new_test_ext_with_authorities_and_sessions(
         })
         .collect();
 
-    ExtBuilder::default()
-        .endowment(ENDOWMENT)
-        .with_endowed_accounts(endowed_accounts)
+    new_ext_builder(addresses_to_fund_tokens)
         .with_initial_authorities(initial_authorities)
         .with_sessions()
         .build()
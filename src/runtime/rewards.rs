@@ -0,0 +1,134 @@
+use crate::mock::{RuntimeEvent, RuntimeOrigin, Staking, System, Test, VaraRewards};
+use crate::runtime_types::{AUTHOR_ROTATION, AUTO_PAYOUT, BLOCK_FIRST_EVENT};
+use crate::staking_helper;
+use crate::types::{AccountId, Balance, VaraRewardsConfig};
+use crate::utils::account_label;
+
+/// ## Rotate the block author
+/// With the author rotation enabled, the validators of the current session author the blocks in
+/// turns (block number modulo the number of validators), so the era reward points and the gas
+/// fees are distributed among them. Disabled by default: every block is authored by the same
+/// account, which gets all the reward points.
+pub fn set_author_rotation(enabled: bool) {
+    AUTHOR_ROTATION.with(|value| *value.borrow_mut() = enabled)
+}
+
+pub fn author_rotation_enabled() -> bool {
+    AUTHOR_ROTATION.with(|value| *value.borrow())
+}
+
+/// ## Pay the stakers at the end of each era
+/// With the auto payout enabled, `payout_stakers` is called for every validator of an era when
+/// the era ends (all the pages of the exposure), so the rewards are paid to the validators and
+/// their nominators (e.g. contracts) without sending the payout messages.
+pub fn set_auto_payout(enabled: bool) {
+    AUTO_PAYOUT.with(|value| *value.borrow_mut() = enabled)
+}
+
+pub fn auto_payout_enabled() -> bool {
+    AUTO_PAYOUT.with(|value| *value.borrow())
+}
+
+/// Vara staking rewards model of the runtime, if it is used (ExtBuilder::with_vara_rewards)
+pub fn vara_rewards() -> Option<VaraRewardsConfig> {
+    VaraRewards::get()
+}
+
+/// ## Vara era payout
+/// Returns the payout of the validators and the remainder of an era with the Vara rewards model
+/// (the same calculation used by the runtime at the end of each era), e.g. to check the APR of
+/// a contract:
+///
+/// ```ignore
+/// let era_duration = era_duration_ms() as u64;
+/// let (validators_payout, _) = vara_era_payout(total_staked, total_issuance(), era_duration);
+/// ```
+///
+/// Panics if the runtime doesn't use the Vara rewards model.
+pub fn vara_era_payout(
+    total_staked: Balance,
+    total_issuance: Balance,
+    era_duration_ms: u64,
+) -> (Balance, Balance) {
+    let config = vara_rewards().expect("The runtime doesn't use the Vara rewards model");
+
+    staking_helper::vara_era_payout(&config, total_staked, total_issuance, era_duration_ms)
+}
+
+/// Account of the staking rewards pool, it funds the rewards with the Vara rewards model
+pub fn rewards_pool() -> AccountId {
+    staking_helper::rewards_pool_account()
+}
+
+pub fn rewards_pool_balance() -> Balance {
+    staking_helper::rewards_pool_balance()
+}
+
+/// Returns the reward points of each validator in the era
+pub fn era_reward_points(era: u32) -> Vec<(AccountId, u32)> {
+    pallet_staking::ErasRewardPoints::<Test>::get(era)
+        .individual
+        .into_iter()
+        .collect()
+}
+
+/// Total stake of the validators and their nominators in the era (the `total_staked` of the
+/// era payout)
+pub fn era_total_stake(era: u32) -> Balance {
+    pallet_staking::ErasTotalStake::<Test>::get(era)
+}
+
+/// Total reward of the validators (and their nominators) in the era, set when the era ends
+pub fn era_validator_reward(era: u32) -> Option<Balance> {
+    pallet_staking::ErasValidatorReward::<Test>::get(era)
+}
+
+/// ## Pay the stakers of an era
+/// Calls `payout_stakers` for every validator of the era until all the pages are paid, the
+/// validators already paid are skipped. Panics if a payout fails for any other reason.
+pub fn payout_era(era: u32) {
+    let validators: Vec<AccountId> =
+        pallet_staking::ErasStakersOverview::<Test>::iter_key_prefix(era).collect();
+
+    for validator in validators {
+        let origin = RuntimeOrigin::signed(validator.clone());
+
+        // Each call pays the next page, it fails with `AlreadyClaimed` when all the pages are paid
+        loop {
+            let Err(error) = Staking::payout_stakers(origin.clone(), validator.clone(), era) else {
+                continue;
+            };
+
+            assert_eq!(
+                error.error,
+                pallet_staking::Error::<Test>::AlreadyClaimed.into(),
+                "Payout of era {era} to {} failed",
+                account_label(validator.clone())
+            );
+
+            break;
+        }
+    }
+}
+
+// Pays the eras that ended in the current block (`EraPaid` events) if the auto payout is enabled
+pub(crate) fn payout_ended_eras() {
+    if !auto_payout_enabled() {
+        return;
+    }
+
+    let first_event = BLOCK_FIRST_EVENT.with(|first| *first.borrow());
+    let ended_eras: Vec<u32> = System::read_events_no_consensus()
+        .skip(first_event)
+        .filter_map(|record| match record.event {
+            RuntimeEvent::Staking(pallet_staking::Event::EraPaid { era_index, .. }) => {
+                Some(era_index)
+            }
+            _ => None,
+        })
+        .collect();
+
+    for era in ended_eras {
+        payout_era(era);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use parity_scale_codec::{Decode, Encode};
+use sp_core::{storage::Storage, twox_128};
+
+use crate::mock::{
+    BlockGasLimit, BlockTime, BondingDuration, EraPayoutValue, ExistentialDeposit, GasMultiplier,
+    Offset, Period, SessionsPerEra, SlashDeferDuration, VaraRewards,
+};
+use crate::types::{Balance, VaraRewardsConfig};
+use crate::utils::storage_keys_with_prefix;
+
+use super::{
+    author_rotation_enabled, auto_payout_enabled, freeze_time, gear_invariant_checks_enabled,
+    set_author_rotation, set_auto_payout, set_gear_invariant_checks, time_frozen, unfreeze_time,
+};
+#[cfg(feature = "try-runtime")]
+use super::{set_try_state_checks, try_state_checks_enabled};
+
+const STATE_FILE_MAGIC: &[u8; 8] = b"vrtstate";
+const STATE_FILE_VERSION: u8 = 3;
+
+/// Runtime settings that are kept by thread (and not in the storage), they are saved
+/// with the snapshot so a restored state keeps the same eras, block time, etc.
+#[derive(Clone, Debug, Encode, Decode)]
+struct RuntimeSettings {
+    block_time: u64,
+    block_gas_limit: u64,
+    sessions_per_era: u32,
+    bonding_duration: u32,
+    slash_defer_duration: u32,
+    period: u64,
+    offset: u64,
+    existential_deposit: Balance,
+    gas_multiplier: common::GasMultiplier<Balance, u64>,
+    era_payout: Balance,
+    vara_rewards: Option<VaraRewardsConfig>,
+    time_frozen: bool,
+    author_rotation: bool,
+    auto_payout: bool,
+    gear_invariant_checks: bool,
+    // Only used with the `try-runtime` feature, it is always saved so the file format is the same
+    try_state_checks: bool,
+}
+
+impl RuntimeSettings {
+    fn current() -> Self {
+        Self {
+            block_time: BlockTime::get(),
+            block_gas_limit: BlockGasLimit::get(),
+            sessions_per_era: SessionsPerEra::get(),
+            bonding_duration: BondingDuration::get(),
+            slash_defer_duration: SlashDeferDuration::get(),
+            period: Period::get(),
+            offset: Offset::get(),
+            existential_deposit: ExistentialDeposit::get(),
+            gas_multiplier: GasMultiplier::get(),
+            era_payout: EraPayoutValue::get(),
+            vara_rewards: VaraRewards::get(),
+            time_frozen: time_frozen(),
+            author_rotation: author_rotation_enabled(),
+            auto_payout: auto_payout_enabled(),
+            gear_invariant_checks: gear_invariant_checks_enabled(),
+            #[cfg(feature = "try-runtime")]
+            try_state_checks: try_state_checks_enabled(),
+            #[cfg(not(feature = "try-runtime"))]
+            try_state_checks: false,
+        }
+    }
+
+    fn apply(&self) {
+        BlockTime::set(self.block_time);
+        BlockGasLimit::set(self.block_gas_limit);
+        SessionsPerEra::set(self.sessions_per_era);
+        BondingDuration::set(self.bonding_duration);
+        SlashDeferDuration::set(self.slash_defer_duration);
+        Period::set(self.period);
+        Offset::set(self.offset);
+        ExistentialDeposit::set(self.existential_deposit);
+        GasMultiplier::set(self.gas_multiplier);
+        EraPayoutValue::set(self.era_payout);
+        VaraRewards::set(self.vara_rewards);
+
+        self.apply_flags();
+    }
+
+    // Settings that are reset with the runtime state (`reset_runtime_state`)
+    fn apply_flags(&self) {
+        if self.time_frozen {
+            freeze_time();
+        } else {
+            unfreeze_time();
+        }
+
+        set_author_rotation(self.author_rotation);
+        set_auto_payout(self.auto_payout);
+        set_gear_invariant_checks(self.gear_invariant_checks);
+        #[cfg(feature = "try-runtime")]
+        set_try_state_checks(self.try_state_checks);
+    }
+}
+
+/// ## State snapshot
+/// Copy of the whole runtime storage (and the runtime settings) at a point of the test,
+/// it can be restored in the current test or used to create new independent tests, so an
+/// expensive setup (uploads, several eras, etc) can be shared between many scenarios.
+#[derive(Clone, Debug, Encode, Decode)]
+pub struct StateSnapshot {
+    storage: BTreeMap<Vec<u8>, Vec<u8>>,
+    settings: RuntimeSettings,
+}
+
+impl StateSnapshot {
+    /// ## Take a snapshot
+    /// Copies the current state, it must be called inside `execute_with`.
+    pub fn take() -> Self {
+        let storage = storage_keys_with_prefix(&[])
+            .into_iter()
+            .filter_map(|key| sp_io::storage::get(&key).map(|value| (key, value.to_vec())))
+            .collect();
+
+        Self {
+            storage,
+            settings: RuntimeSettings::current(),
+        }
+    }
+
+    /// ## Take a snapshot of some pallets
+    /// Copies only the storage of the given pallets (names as in the runtime, e.g. "Balances",
+    /// "GearProgram"), it can be loaded over the genesis of other tests with `ExtBuilder::with_state`.
+    pub fn take_pallets(pallets: &[&str]) -> Self {
+        let storage = pallets
+            .iter()
+            .flat_map(|pallet| storage_keys_with_prefix(&twox_128(pallet.as_bytes())))
+            .filter_map(|key| sp_io::storage::get(&key).map(|value| (key, value.to_vec())))
+            .collect();
+
+        Self {
+            storage,
+            settings: RuntimeSettings::current(),
+        }
+    }
+
+    /// ## Save the snapshot to a file
+    /// The file can be checked in and loaded in other tests with `StateSnapshot::load`.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut bytes = STATE_FILE_MAGIC.to_vec();
+        bytes.push(STATE_FILE_VERSION);
+        self.encode_to(&mut bytes);
+
+        std::fs::write(path, bytes)
+    }
+
+    /// ## Load a snapshot from a file
+    /// Reads a file created with `save`.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+
+        let Some(encoded) = bytes.strip_prefix(STATE_FILE_MAGIC.as_slice()) else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "not a vrtest state file",
+            ));
+        };
+
+        let Some((&version, mut encoded)) = encoded.split_first() else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "state file without version",
+            ));
+        };
+
+        if version != STATE_FILE_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported state file version {version}"),
+            ));
+        }
+
+        Self::decode(&mut encoded).map_err(|error| Error::new(ErrorKind::InvalidData, error))
+    }
+
+    /// ## Restore the snapshot
+    /// Replaces the current state with the snapshot state, it must be called inside `execute_with`.
+    pub fn restore(&self) {
+        // `clear_prefix` with an empty prefix is not allowed, keys are removed one by one
+        for key in storage_keys_with_prefix(&[]) {
+            sp_io::storage::clear(&key);
+        }
+
+        for (key, value) in self.storage.iter() {
+            sp_io::storage::set(key, value);
+        }
+
+        self.settings.apply();
+    }
+
+    /// ## New test from the snapshot
+    /// Creates new externalities with the snapshot state, changes made in it does not
+    /// affect the snapshot or other tests created from it.
+    pub fn to_test_ext(&self) -> sp_io::TestExternalities {
+        self.settings.apply();
+
+        let mut ext: sp_io::TestExternalities = Storage {
+            top: self.storage.clone(),
+            children_default: Default::default(),
+        }
+        .into();
+
+        ext.execute_with(super::reset_runtime_state);
+
+        // Keep the flags from the snapshot (reset_runtime_state disables them)
+        self.settings.apply_flags();
+
+        ext
+    }
+
+    /// ## Execute in a new test from the snapshot
+    /// Runs the closure in new externalities created with `to_test_ext`.
+    pub fn execute_with<R>(&self, execute: impl FnOnce() -> R) -> R {
+        self.to_test_ext().execute_with(execute)
+    }
+
+    pub(crate) fn storage(&self) -> &BTreeMap<Vec<u8>, Vec<u8>> {
+        &self.storage
+    }
+
+    /// Number of storage entries saved in the snapshot
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Returns true if the snapshot has no storage entries
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+}
@@ -0,0 +1,77 @@
+use frame_support::traits::{TryState, TryStateSelect};
+use frame_system::pallet_prelude::BlockNumberFor;
+use sp_runtime::TryRuntimeError;
+
+use crate::mock::{AllPalletsWithSystem, System, Test};
+use crate::runtime_types::{TRY_STATE_CHECKED_BLOCKS, TRY_STATE_CHECKS};
+
+/// Pallets checked by default: the pallets that contracts can change through messages and builtins
+pub const TRY_STATE_PALLETS: [&str; 9] = [
+    "Balances",
+    "Staking",
+    "Proxy",
+    "GearProgram",
+    "GearMessenger",
+    "GearScheduler",
+    "GearBank",
+    "Gear",
+    "GearGas",
+];
+
+/// ## Enable the try_state checks
+/// When enabled, the `try_state` hooks of the `TRY_STATE_PALLETS` are run after each block
+/// (including the idle blocks of the fast forward), the test fails if one of them returns an error.
+pub fn set_try_state_checks(enabled: bool) {
+    TRY_STATE_CHECKS.with(|value| *value.borrow_mut() = enabled)
+}
+
+pub fn try_state_checks_enabled() -> bool {
+    TRY_STATE_CHECKS.with(|value| *value.borrow())
+}
+
+/// ## Run the try_state checks
+/// Runs the `try_state` hooks of the given pallets (names as in the runtime) in the current block.
+pub fn try_state(pallets: &[&str]) -> Result<(), TryRuntimeError> {
+    let pallets = pallets
+        .iter()
+        .map(|pallet| pallet.as_bytes().to_vec())
+        .collect();
+
+    <AllPalletsWithSystem as TryState<BlockNumberFor<Test>>>::try_state(
+        System::block_number(),
+        TryStateSelect::Only(pallets),
+    )
+}
+
+/// ## Assert the try_state checks
+/// Fails the test if the `try_state` hooks of the `TRY_STATE_PALLETS` return an error, it can be
+/// called at the end of the test.
+pub fn assert_try_state() {
+    if let Err(error) = try_state(&TRY_STATE_PALLETS) {
+        panic!(
+            "try_state checks failed in block {}: {error:?}",
+            System::block_number()
+        );
+    }
+}
+
+/// ## Blocks checked
+/// Number of blocks checked after their execution by the try_state checks since the test started
+pub fn try_state_checked_blocks() -> u64 {
+    TRY_STATE_CHECKED_BLOCKS.with(|count| *count.borrow())
+}
+
+// Runs the try_state checks after a block if they are enabled
+pub(crate) fn check_try_state_after_block() {
+    if !try_state_checks_enabled() {
+        return;
+    }
+
+    assert_try_state();
+    TRY_STATE_CHECKED_BLOCKS.with(|count| *count.borrow_mut() += 1);
+}
+
+pub(crate) fn reset_try_state_checks() {
+    set_try_state_checks(false);
+    TRY_STATE_CHECKED_BLOCKS.with(|count| *count.borrow_mut() = 0);
+}
@@ -1,5 +1,10 @@
 use gear_core::ids::ActorId;
 use core::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use crate::types::{
+    AccountId, BlockExecutionInfo, BlockHookId, QueueNotProcessedReport, RuntimeEvent,
+};
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct ExecutionTraceFrame {
@@ -9,7 +14,37 @@ pub struct ExecutionTraceFrame {
     pub is_success: bool,
 }
 
+pub type BeforeBlockHook = Rc<RefCell<dyn FnMut(u64)>>;
+pub type AfterBlockHook = Rc<RefCell<dyn FnMut(u64, &[RuntimeEvent])>>;
+pub type EventsResetHook = Rc<RefCell<dyn FnMut()>>;
+
 thread_local! {
     pub static DEBUG_EXECUTION_TRACE: RefCell<Vec<ExecutionTraceFrame>> = const { RefCell::new(Vec::new()) };
-    pub static IN_TRANSACTION: RefCell<bool> = const { RefCell::new(false) };
+    pub static TRANSACTION_DEPTH: RefCell<u32> = const { RefCell::new(0) };
+    pub static FAST_FORWARD: RefCell<bool> = const { RefCell::new(false) };
+    pub static TIME_FROZEN: RefCell<bool> = const { RefCell::new(false) };
+    pub static CODE_CACHE_ENABLED: RefCell<bool> = const { RefCell::new(false) };
+    pub static GEAR_INVARIANT_CHECKS: RefCell<bool> = const { RefCell::new(false) };
+    #[cfg(feature = "try-runtime")]
+    pub static TRY_STATE_CHECKS: RefCell<bool> = const { RefCell::new(false) };
+    #[cfg(feature = "try-runtime")]
+    pub static TRY_STATE_CHECKED_BLOCKS: RefCell<u64> = const { RefCell::new(0) };
+    pub static GAS_ALLOWANCE: RefCell<Option<u64>> = const { RefCell::new(None) };
+    pub static LAST_BLOCK_INFO: RefCell<Option<BlockExecutionInfo>> = const { RefCell::new(None) };
+    pub static QUEUE_NOT_PROCESSED_ALLOWED: RefCell<bool> = const { RefCell::new(false) };
+    pub static SKIPPED_GEAR_RUNS: RefCell<u32> = const { RefCell::new(0) };
+    pub static AUTHOR_ROTATION: RefCell<bool> = const { RefCell::new(false) };
+    pub static AUTO_PAYOUT: RefCell<bool> = const { RefCell::new(false) };
+    pub static BEFORE_BLOCK_HOOKS: RefCell<Vec<(BlockHookId, BeforeBlockHook)>> = const { RefCell::new(Vec::new()) };
+    pub static AFTER_BLOCK_HOOKS: RefCell<Vec<(BlockHookId, AfterBlockHook)>> = const { RefCell::new(Vec::new()) };
+    pub static EVENTS_RESET_HOOKS: RefCell<Vec<(BlockHookId, EventsResetHook)>> = const { RefCell::new(Vec::new()) };
+    pub static NEXT_BLOCK_HOOK_ID: RefCell<u64> = const { RefCell::new(0) };
+    pub static BLOCK_FIRST_EVENT: RefCell<usize> = const { RefCell::new(0) };
+    // Names of the test accounts, kept for the whole test thread (accounts are named before the
+    // test externalities are built)
+    pub static ACCOUNT_NAMES: RefCell<BTreeMap<AccountId, String>> = const { RefCell::new(BTreeMap::new()) };
+    pub static RANDOM_SEED: RefCell<Option<u64>> = const { RefCell::new(None) };
+    pub static RANDOM_OUTPUT: RefCell<Option<[u8; 32]>> = const { RefCell::new(None) };
+    pub static RANDOM_OUTPUTS_BY_BLOCK: RefCell<BTreeMap<u64, [u8; 32]>> = const { RefCell::new(BTreeMap::new()) };
+    pub static QUEUE_NOT_PROCESSED_REPORTS: RefCell<Vec<QueueNotProcessedReport>> = const { RefCell::new(Vec::new()) };
 }
\ No newline at end of file
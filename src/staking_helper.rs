@@ -1,11 +1,23 @@
-use crate::mock::{Staking, Test};
-use crate::types::{AccountId, Balance, EXISTENTIAL_DEPOSIT};
+use crate::mock::{Balances, EraPayoutValue, Staking, StakingRewardsPalletId, Test, VaraRewards};
+use crate::types::{AccountId, Balance, VaraRewardsConfig, EXISTENTIAL_DEPOSIT};
 use frame_election_provider_support::{
     bounds::{ElectionBounds, ElectionBoundsBuilder},
     onchain, SequentialPhragmen,
 };
-use frame_support::parameter_types;
-use sp_runtime::{traits::ConstU32, Perbill};
+use core::marker::PhantomData;
+use frame_support::{
+    parameter_types,
+    traits::{Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced, WithdrawReasons},
+};
+use sp_runtime::{
+    traits::{AccountIdConversion, ConstU32, Saturating},
+    Perbill, Perquintill,
+};
+
+// Milliseconds in a year (365.25 days), the era payout is the share of the annual inflation
+const MILLISECS_PER_YEAR: u64 = 1_000 * 3_600 * 24 * 36_525 / 100;
+
+type PositiveImbalance = <Balances as Currency<AccountId>>::PositiveImbalance;
 
 parameter_types! {
     pub const BlockHashCount: u64 = 250;
@@ -13,15 +25,100 @@ parameter_types! {
     pub ElectionBoundsOnChain: ElectionBounds = ElectionBoundsBuilder::default().build();
 }
 
-// Fixed payout for each era
-pub struct FixedEraPayout<const PAYOUT: u128>;
-impl<const PAYOUT: u128> pallet_staking::EraPayout<u128> for FixedEraPayout<PAYOUT> {
+// Fixed payout for each era (the payout can be changed per test)
+pub struct FixedEraPayout<Payout>(PhantomData<Payout>);
+impl<Payout: Get<u128>> pallet_staking::EraPayout<u128> for FixedEraPayout<Payout> {
     fn era_payout(
         _total_staked: u128,
         _total_issuance: u128,
         _era_duration_millis: u64,
     ) -> (u128, u128) {
-        (PAYOUT, 0)
+        (Payout::get(), 0)
+    }
+}
+
+// Era payout of the test runtime: the Vara rewards model if it is set (ExtBuilder::with_vara_rewards),
+// the fixed payout otherwise
+pub struct TestEraPayout;
+impl pallet_staking::EraPayout<u128> for TestEraPayout {
+    fn era_payout(
+        total_staked: u128,
+        total_issuance: u128,
+        era_duration_millis: u64,
+    ) -> (u128, u128) {
+        match VaraRewards::get() {
+            Some(config) => {
+                vara_era_payout(&config, total_staked, total_issuance, era_duration_millis)
+            }
+            None => FixedEraPayout::<EraPayoutValue>::era_payout(
+                total_staked,
+                total_issuance,
+                era_duration_millis,
+            ),
+        }
+    }
+}
+
+// Payout of the validators and remainder of an era with the Vara rewards model, the tokens of the
+// rewards pool are not in circulation
+pub(crate) fn vara_era_payout(
+    config: &VaraRewardsConfig,
+    total_staked: Balance,
+    total_issuance: Balance,
+    era_duration_millis: u64,
+) -> (Balance, Balance) {
+    let circulating = total_issuance.saturating_sub(rewards_pool_balance());
+    let stakeable = Perquintill::one().saturating_sub(config.non_stakeable) * circulating;
+
+    if stakeable == 0 {
+        return (0, 0);
+    }
+
+    // Same curve as the Vara runtime: linear up to the ideal stake, exponential decay above it
+    let stake = Perquintill::from_rational(total_staked.min(stakeable), stakeable);
+    let adjustment =
+        pallet_staking_reward_fn::compute_inflation(stake, config.ideal_stake, config.falloff);
+    let annual_inflation = config
+        .min_inflation
+        .saturating_add(config.target_inflation.saturating_sub(config.min_inflation) * adjustment);
+
+    let era_share = Perquintill::from_rational(era_duration_millis, MILLISECS_PER_YEAR);
+    let payout = era_share * (annual_inflation * circulating);
+    let max_payout = era_share * (config.max_roi * total_staked);
+    let validators_payout = payout.min(max_payout);
+
+    (validators_payout, payout - validators_payout)
+}
+
+pub(crate) fn rewards_pool_account() -> AccountId {
+    StakingRewardsPalletId::get().into_account_truncating()
+}
+
+pub(crate) fn rewards_pool_balance() -> Balance {
+    Balances::free_balance(rewards_pool_account())
+}
+
+// Staking rewards are minted when they are paid, with the Vara rewards model the same amount is
+// burned from the rewards pool (while it has funds), so the rewards are funded by the pool
+pub struct RewardsPool;
+impl OnUnbalanced<PositiveImbalance> for RewardsPool {
+    fn on_nonzero_unbalanced(minted: PositiveImbalance) {
+        if VaraRewards::get().is_none() {
+            return;
+        }
+
+        let pool = rewards_pool_account();
+        let existential_deposit = <Test as pallet_balances::Config>::ExistentialDeposit::get();
+        let available = Balances::free_balance(&pool).saturating_sub(existential_deposit);
+
+        if let Ok(burned) = Balances::withdraw(
+            &pool,
+            minted.peek().min(available),
+            WithdrawReasons::TRANSFER,
+            ExistenceRequirement::KeepAlive,
+        ) {
+            let _ = minted.offset(burned);
+        }
     }
 }
 
@@ -4,12 +4,34 @@ use parity_scale_codec::{Decode, Encode, MaxEncodedLen};
 use primitive_types::H256;
 use primitive_types::U256;
 use scale_info::TypeInfo;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sp_core::bytes::{from_hex, to_hex};
+use sp_core::crypto::{AccountId32, PublicError, Ss58AddressFormat, Ss58Codec};
 
-#[derive(
-    Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Encode, Decode, TypeInfo, MaxEncodedLen,
-)]
+/// SS58 prefix of the Vara addresses
+pub const VARA_SS58_PREFIX: u16 = 137;
+
+/// ## 32-byte actor id
+/// Displayed as a Vara SS58 address, and parsed from SS58 (any prefix) or hex (`0x...`)
+/// addresses, so the fixtures can use the same addresses as the frontends:
+///
+/// ```ignore
+/// let actor: ActorId32 = "kGkLEU3e3XXkJp2WK4eNpVmSab5xUNL9QtmLPh8QfCL2EgotW".parse().unwrap();
+/// assert_eq!(actor.to_hex().parse::<ActorId32>().unwrap(), actor);
+/// ```
+///
+/// With serde, human readable formats (e.g. json) use the SS58 address and binary formats the bytes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Encode, Decode, TypeInfo, MaxEncodedLen)]
 pub struct ActorId32([u8; 32]);
 
+/// ## Error parsing an ActorId32
+#[derive(Debug)]
+pub enum ActorId32ParseError {
+    InvalidHex(String),
+    InvalidLength(usize),
+    InvalidSs58(PublicError),
+}
+
 impl ActorId32 {
     pub fn into_actor_id(self) -> ActorId {
         ActorId::from(self.0)
@@ -17,6 +39,99 @@ impl ActorId32 {
     pub fn as_bytes(&self) -> &[u8; 32] {
         &self.0
     }
+
+    /// Vara SS58 address (prefix 137)
+    pub fn to_ss58(&self) -> String {
+        self.to_ss58_with_prefix(VARA_SS58_PREFIX)
+    }
+
+    /// SS58 address with another prefix (e.g. 42 for the generic Substrate addresses)
+    pub fn to_ss58_with_prefix(&self, prefix: u16) -> String {
+        AccountId32::new(self.0).to_ss58check_with_version(Ss58AddressFormat::custom(prefix))
+    }
+
+    /// Hex address with the `0x` prefix
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0, false)
+    }
+
+    /// Parses a SS58 address with any prefix
+    pub fn from_ss58(address: &str) -> Result<Self, ActorId32ParseError> {
+        let (account, _) = AccountId32::from_ss58check_with_version(address)
+            .map_err(ActorId32ParseError::InvalidSs58)?;
+
+        Ok(Self(account.into()))
+    }
+
+    /// Parses a hex address (with or without the `0x` prefix)
+    pub fn from_hex(address: &str) -> Result<Self, ActorId32ParseError> {
+        let bytes = from_hex(address)
+            .map_err(|error| ActorId32ParseError::InvalidHex(error.to_string()))?;
+
+        <[u8; 32]>::try_from(bytes.as_slice())
+            .map(Self)
+            .map_err(|_| ActorId32ParseError::InvalidLength(bytes.len()))
+    }
+}
+
+impl core::fmt::Display for ActorId32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.to_ss58())
+    }
+}
+
+impl core::fmt::Debug for ActorId32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "ActorId32({})", self.to_ss58())
+    }
+}
+
+impl core::str::FromStr for ActorId32 {
+    type Err = ActorId32ParseError;
+
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        if address.starts_with("0x") {
+            Self::from_hex(address)
+        } else {
+            Self::from_ss58(address)
+        }
+    }
+}
+
+impl core::fmt::Display for ActorId32ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidHex(error) => write!(f, "Invalid hex address: {error}"),
+            Self::InvalidLength(length) => {
+                write!(f, "Invalid address length: {length} bytes (32 expected)")
+            }
+            Self::InvalidSs58(error) => write!(f, "Invalid SS58 address: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ActorId32ParseError {}
+
+impl Serialize for ActorId32 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_ss58())
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ActorId32 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let address = String::deserialize(deserializer)?;
+
+            address.parse().map_err(de::Error::custom)
+        } else {
+            <[u8; 32]>::deserialize(deserializer).map(Self)
+        }
+    }
 }
 
 impl From<[u8; 32]> for ActorId32 {
@@ -34,6 +149,7 @@ impl From<u64> for ActorId32 {
     }
 }
 
+// U256 bytes are taken in little-endian order
 impl From<U256> for ActorId32 {
     fn from(x: U256) -> Self {
         let mut b = [0u8; 32];
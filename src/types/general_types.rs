@@ -1,8 +1,18 @@
 use crate::mock::Test;
-use common::{event::DispatchStatus, GasProvider};
-use sp_runtime::DispatchError;
+use crate::utils::account_label;
+use common::{event::DispatchStatus, GasProvider, Origin};
+use parity_scale_codec::{Decode, Encode};
+use sp_runtime::{DispatchError, Perquintill};
 
+pub use crate::mock::RuntimeEvent;
+
+/// ## Account id of the runtime
+/// `u64` by default, with the `account-id32` feature the runtime uses 32-byte accounts like Vara,
+/// so contract accounts are the full program id (use `account_id` to get the account of an index).
+#[cfg(not(feature = "account-id32"))]
 pub type AccountId = u64;
+#[cfg(feature = "account-id32")]
+pub type AccountId = sp_runtime::AccountId32;
 pub type BlockNumber = u64;
 pub type Balance = u128;
 pub type Block = frame_system::mocking::MockBlock<Test>;
@@ -13,20 +23,30 @@ pub(crate) type QueueOf<T> = pallet_gear_messenger::Dispatches<T>;
 pub(crate) type GasHandlerOf<T> = <<T as pallet_gear::Config>::GasProvider as GasProvider>::GasTree;
 pub(crate) type GasTreeOf<T> = pallet_gear_gas::GasNodes<T>;
 
-pub(crate) const BLOCK_AUTHOR: AccountId = 10; // [TODO]: given by val_1_stash
+// Author of the blocks without the author rotation (runtime::set_author_rotation)
+pub(crate) const BLOCK_AUTHOR: u64 = 10;
 
 pub(crate) const EXISTENTIAL_DEPOSIT: u128 = 1 * UNITS;
 pub const ENDOWMENT: u128 = 1_000 * UNITS;
 
 pub(crate) const UNITS: u128 = 1_000_000_000_000; // 10^(-12) precision
-#[allow(unused)]
 pub(crate) const MILLISECS_PER_BLOCK: u64 = 3_000;
 
 pub(crate) const DEFAULT_GAS_LIMIT: u64 = 20_000_000_000;
+pub(crate) const DEFAULT_BLOCK_GAS_LIMIT: u64 = 100_000_000_000_000;
 
 // Staking consts
 
 pub const SESSION_DURATION_IN_BLOCKS: u64 = 2_400; // 250;
+pub(crate) const SESSIONS_PER_ERA: u32 = 6;
+pub(crate) const BONDING_DURATION: u32 = 14;
+pub(crate) const ERA_PAYOUT: u128 = 100 * UNITS;
+// Lock id of the bonded tokens (runtime::lock)
+pub const STAKING_LOCK_ID: [u8; 8] = *b"staking ";
+
+// Gear bank consts
+
+pub(crate) const VALUE_PER_GAS: u128 = 100;
 
 #[derive(PartialEq)]
 pub enum StakingEventType {
@@ -35,6 +55,14 @@ pub enum StakingEventType {
     Withdrawn,
 }
 
+/// ## Account of an index
+/// Returns the account of the index (`SIGNER = 1`, etc) in both account modes: the index itself
+/// with `u64` accounts, and the 32-byte account of the same origin with the `account-id32`
+/// feature, so the contracts see the same `msg::source()` in both modes.
+pub fn account_id(index: u64) -> AccountId {
+    AccountId::from_origin(index.into_origin())
+}
+
 // public consts
 
 pub const ONE_TOKEN: u128 = UNITS;
@@ -60,9 +88,239 @@ pub enum ContractCommandError {
     TimeOut,
 }
 
+/// ## Block execution info
+/// Gas and queue data of the last block executed by the runtime (runtime::last_block_info)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BlockExecutionInfo {
+    /// Block number that was executed
+    pub block: u64,
+    /// Gas allowance that was not used by the Gear execution
+    pub gas_allowance_left: u64,
+    /// Dispatches that were left in the message queue
+    pub dispatches_left: usize,
+}
+
+/// ## Queue not processed report
+/// Block where the Gear queue was not processed (`QueueNotProcessed` event or `Gear::run` error),
+/// it is recorded when `runtime::allow_queue_not_processed(true)` is set
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct QueueNotProcessedReport {
+    /// Block number where the queue was not processed
+    pub block: u64,
+    /// Dispatches that were left in the message queue
+    pub dispatches_left: usize,
+    /// Error of `Gear::run`, None if the run was skipped (runtime::skip_gear_run)
+    pub error: Option<DispatchError>,
+}
+
 /// ## Command response
 pub enum ContractResponse<R> {
     Response(R),
     OkNoReply,
     Waited,
 }
+
+/// ## Block hook id
+/// Returned when a block hook is added, it is used to remove the hook (runtime::remove_block_hook)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BlockHookId(pub(crate) u64);
+
+/// ## Leaked gas nodes
+/// Gas nodes of an origin that are still in the gas tree when there are no messages in the queue
+/// and no tasks in the scheduler
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LeakedGasNodes {
+    /// Account that paid for the gas
+    pub origin: AccountId,
+    /// Number of leaked gas nodes
+    pub nodes: usize,
+    /// Gas left in the leaked nodes
+    pub gas: u64,
+}
+
+/// ## Gear invariants report
+/// State of the gear bank and the gas tree when the invariants are checked (runtime::check_gear_invariants)
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GearInvariantsReport {
+    /// Block number where the invariants were checked
+    pub block: u64,
+    /// Free balance of the gear bank account
+    pub bank_balance: Balance,
+    /// Sum of the gas deposits (as value) in the gear bank
+    pub gas_deposits: Balance,
+    /// Sum of the value deposits in the gear bank
+    pub value_deposits: Balance,
+    /// Value in the gear bank that is not owned by any account
+    pub unused_value: Balance,
+    /// Gas nodes left per origin, only checked when the queue and the scheduler are empty
+    pub leaked_gas_nodes: Vec<LeakedGasNodes>,
+}
+
+impl GearInvariantsReport {
+    /// Deposits that the gear bank account must hold
+    pub fn required_bank_balance(&self) -> Balance {
+        self.gas_deposits
+            .saturating_add(self.value_deposits)
+            .saturating_add(self.unused_value)
+    }
+
+    /// Returns true if the bank holds all the deposits and there are no leaked gas nodes
+    pub fn is_ok(&self) -> bool {
+        self.bank_balance >= self.required_bank_balance() && self.leaked_gas_nodes.is_empty()
+    }
+}
+
+impl core::fmt::Display for GearInvariantsReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "Gear invariants in block {}:", self.block)?;
+
+        let required_bank_balance = self.required_bank_balance();
+
+        if self.bank_balance < required_bank_balance {
+            writeln!(
+                f,
+                "  - GearBank balance ({}) is lower than its deposits ({}): gas {}, value {}, unused value {}",
+                self.bank_balance,
+                required_bank_balance,
+                self.gas_deposits,
+                self.value_deposits,
+                self.unused_value,
+            )?;
+        }
+
+        for leaked in self.leaked_gas_nodes.iter() {
+            writeln!(
+                f,
+                "  - Leaked gas nodes of origin {}: {} nodes with {} gas",
+                account_label(leaked.origin.clone()),
+                leaked.nodes,
+                leaked.gas,
+            )?;
+        }
+
+        if self.is_ok() {
+            writeln!(f, "  - OK")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// ## Vara staking rewards model
+/// Inflation model of the Vara staking rewards used for the era payouts instead of the fixed
+/// payout (ExtBuilder::with_vara_rewards). The annual inflation goes from `min_inflation` (nothing
+/// staked) to `target_inflation` (the staked share of the stakeable tokens is `ideal_stake`), and
+/// above the ideal stake it decreases exponentially (halved each `falloff`). The validators get at
+/// most `max_roi` of the staked tokens per year, and the rewards are paid from the rewards pool
+/// (`pool_balance` in the genesis) while it has funds, the tokens are minted when it is empty.
+///
+/// The default values are the parameters of the Vara mainnet (except the pool balance).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct VaraRewardsConfig {
+    /// Ideal staked share of the stakeable tokens
+    pub ideal_stake: Perquintill,
+    /// Annual inflation with the ideal stake
+    pub target_inflation: Perquintill,
+    /// Annual inflation with nothing staked
+    pub min_inflation: Perquintill,
+    /// Maximum annual return of the staked tokens
+    pub max_roi: Perquintill,
+    /// Stake increase (above the ideal stake) that halves the inflation
+    pub falloff: Perquintill,
+    /// Share of the tokens in circulation that can't be staked (e.g. vesting)
+    pub non_stakeable: Perquintill,
+    /// Balance of the rewards pool in the genesis
+    pub pool_balance: Balance,
+}
+
+impl Default for VaraRewardsConfig {
+    fn default() -> Self {
+        Self {
+            ideal_stake: Perquintill::from_percent(85),
+            target_inflation: Perquintill::from_rational(578_u64, 10_000_u64),
+            min_inflation: Perquintill::from_percent(1),
+            max_roi: Perquintill::from_percent(30),
+            falloff: Perquintill::from_percent(2),
+            non_stakeable: Perquintill::from_rational(4_108_u64, 10_000_u64),
+            pool_balance: 0,
+        }
+    }
+}
+
+/// ## Balance delta
+/// Change of the balance of an account since a `BalanceTracker` started, split by the cause of
+/// the change (runtime::BalanceTracker)
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BalanceDelta {
+    /// Tracked account
+    pub account: AccountId,
+    /// Change of the free balance
+    pub free: i128,
+    /// Change of the frozen balance (staking locks, etc)
+    pub frozen: i128,
+    /// Change of the reserved balance
+    pub reserved: i128,
+    /// Gas paid for the messages sent by the account (including the gas still deposited in the gear bank)
+    pub gas_paid: i128,
+    /// Value sent with messages
+    pub value_sent: Balance,
+    /// Value received from messages (including the value returned by failed messages)
+    pub value_received: Balance,
+    /// Tokens transferred from other accounts (not with messages)
+    pub transferred_in: Balance,
+    /// Tokens transferred to other accounts (not with messages, e.g. the existential deposit of new programs)
+    pub transferred_out: Balance,
+    /// Staking rewards paid to the account
+    pub rewards: Balance,
+    /// Tokens slashed from the account
+    pub slashed: Balance,
+    /// Gas fees received as block author
+    pub fees_received: Balance,
+}
+
+impl BalanceDelta {
+    /// Change of the free balance explained by the tracked causes
+    pub fn explained(&self) -> i128 {
+        let income = self.value_received + self.transferred_in + self.rewards + self.fees_received;
+        let expenses = self.value_sent + self.transferred_out + self.slashed;
+
+        income as i128 - expenses as i128 - self.gas_paid
+    }
+
+    /// Change of the free balance that is not explained by the tracked causes (e.g. balances set
+    /// directly in the storage)
+    pub fn unexplained(&self) -> i128 {
+        self.free - self.explained()
+    }
+}
+
+impl core::fmt::Display for BalanceDelta {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(
+            f,
+            "Balance delta of {}: free {:+}, frozen {:+}, reserved {:+}",
+            account_label(self.account.clone()),
+            self.free,
+            self.frozen,
+            self.reserved,
+        )?;
+
+        let causes: [(&str, i128); 9] = [
+            ("gas paid", -self.gas_paid),
+            ("value sent", -(self.value_sent as i128)),
+            ("value received", self.value_received as i128),
+            ("transferred in", self.transferred_in as i128),
+            ("transferred out", -(self.transferred_out as i128)),
+            ("rewards", self.rewards as i128),
+            ("slashed", -(self.slashed as i128)),
+            ("fees received", self.fees_received as i128),
+            ("unexplained", self.unexplained()),
+        ];
+
+        for (cause, amount) in causes.iter().filter(|(_, amount)| *amount != 0) {
+            writeln!(f, "  - {cause}: {amount:+}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use crate::runtime_types::ACCOUNT_NAMES;
+use crate::types::AccountId;
+
+/// Returns the name of the account, if it was named
+pub fn account_name(id: AccountId) -> Option<String> {
+    ACCOUNT_NAMES.with(|names| names.borrow().get(&id).cloned())
+}
+
+/// Returns the name of the account, or the id if it has no name (SS58 address with 32-byte accounts)
+pub fn account_label(id: AccountId) -> String {
+    account_name(id.clone()).unwrap_or_else(|| id.to_string())
+}
+
+/// ## Raw storage keys with prefix
+/// Returns every storage key (in the current externalities) that starts with the given prefix,
+/// it is used to inspect pallets storages that are not public.
+pub(crate) fn storage_keys_with_prefix(prefix: &[u8]) -> Vec<Vec<u8>> {
+    let mut keys = vec![];
+    let mut current_key = prefix.to_vec();
+
+    while let Some(next_key) = sp_io::storage::next_key(&current_key) {
+        if !next_key.starts_with(prefix) {
+            break;
+        }
+
+        keys.push(next_key.clone());
+        current_key = next_key;
+    }
+
+    keys
+}
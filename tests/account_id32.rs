@@ -0,0 +1,67 @@
+#![cfg(feature = "account-id32")]
+
+use common::{ActorId, Origin};
+use vrtest::{
+    contract::{Contract, builders::UploadWasmT},
+    runtime::*,
+    types::{
+        account_id,
+        AccountId,
+        actorid32::ActorId32,
+        ContractResponse,
+        ONE_TOKEN,
+    }
+};
+
+use contract::WASM_BINARY;
+const SIGNER: u64 = 1;
+
+#[test]
+pub fn contract_account_is_the_program_id() {
+    let signer = account_id(SIGNER);
+
+    new_test_ext(vec![signer.clone()]).execute_with(|| {
+        assert_eq!(balance_from_user(signer.clone()), 1_000 * ONE_TOKEN);
+
+        let contract = Contract::upload_sails_contract()
+            .signer(signer.clone())
+            .salt("contract")
+            .app_constructor_name("New")
+            .init_payload(())
+            .wasm(WASM_BINARY)
+            .upload();
+
+        // No truncation: the account is the full program id
+        let contract_account: [u8; 32] = contract.address().into();
+        assert_eq!(contract.account(), AccountId::from(contract_account));
+
+        let result = contract.new_command()
+            .signer(signer.clone())
+            .service_name("ContractService")
+            .method_name("Hello")
+            .add_arg(())
+            .send_recv::<String>();
+
+        let Ok(ContractResponse::Response(response)) = result else {
+            panic!("Incorrect response");
+        };
+
+        // Contracts see the same source as with u64 accounts
+        assert_eq!(response, format!("Hello {}", ActorId::from(SIGNER.into_origin())));
+        assert_eq!(ActorId32::from(account_to_actor_id(signer)), ActorId32::from(SIGNER));
+    });
+}
+
+#[test]
+pub fn named_accounts_are_32_bytes() {
+    let alice = account("alice");
+
+    new_ext_builder(vec![])
+        .with_funded_accounts([&alice])
+        .build()
+        .execute_with(|| {
+            assert_eq!(alice.free_balance(), 1_000 * ONE_TOKEN);
+            assert_eq!(alice.actor_id32().as_bytes(), AsRef::<[u8; 32]>::as_ref(&alice.id()));
+            assert_eq!(account_label(alice.id()), "alice");
+        });
+}
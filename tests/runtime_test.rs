@@ -1,5 +1,10 @@
+// The tests use `u64` accounts, the 32-byte accounts mode is tested in `account_id32.rs`
+#![cfg(not(feature = "account-id32"))]
+
+use sp_runtime::testing::UintAuthorityId;
 use vrtest::{
-    runtime::*
+    runtime::*,
+    types::{actorid32::ActorId32, ONE_TOKEN},
 };
 
 const SIGNER: u64 = 1;
@@ -361,3 +366,696 @@ pub fn test_correct_block_count_for_sessions_and_eras() {
 }
 
 
+
+#[test]
+pub fn test_advance_sessions_and_eras() {
+    let authorities = vec![
+        (VAL_1_STASH, VAL_1_STASH_AUTH_ID),
+    ];
+
+    new_test_ext_with_authorities_and_sessions(vec![SIGNER], authorities).execute_with(|| {
+        advance_to_next_session();
+
+        assert_eq!(
+            current_block(),
+            2_401
+        );
+
+        assert_eq!(
+            current_session_index(),
+            1
+        );
+
+        // Active era 1 starts at session 6
+        advance_to_next_era();
+
+        assert_eq!(
+            current_block(),
+            14_401
+        );
+
+        assert_eq!(
+            current_session_index(),
+            6
+        );
+
+        assert_eq!(
+            current_era_index(),
+            1
+        );
+
+        advance_eras(2);
+
+        assert_eq!(
+            current_session_index(),
+            18
+        );
+
+        assert_eq!(
+            current_era_index(),
+            3
+        );
+
+        assert_eq!(
+            current_timestamp(),
+            current_block() * block_in_ms()
+        );
+    });
+}
+
+#[test]
+pub fn test_fast_forward_keeps_sessions_and_eras() {
+    let authorities = vec![
+        (VAL_1_STASH, VAL_1_STASH_AUTH_ID),
+    ];
+
+    new_test_ext_with_authorities_and_sessions(vec![SIGNER], authorities).execute_with(|| {
+        set_fast_forward(true);
+
+        // Same checkpoints as test_correct_block_count_for_sessions_and_eras
+        run_for_n_blocks(12_000, None);
+
+        assert_eq!(
+            current_block(),
+            12_001
+        );
+
+        assert_eq!(
+            current_session_index(),
+            5
+        );
+
+        assert_eq!(
+            current_era(),
+            1
+        );
+
+        fast_forward_blocks(14_400);
+
+        assert_eq!(
+            current_block(),
+            26_401
+        );
+
+        assert_eq!(
+            current_session_index(),
+            11
+        );
+
+        assert_eq!(
+            current_era(),
+            2
+        );
+
+        assert_eq!(
+            gear_block_number(),
+            current_block()
+        );
+    });
+}
+
+#[test]
+pub fn test_run_until() {
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let stop_block = run_until(|| current_block() == 10, 20);
+
+        assert_eq!(
+            stop_block,
+            Some(10)
+        );
+
+        // Condition already met, no blocks are run
+        assert_eq!(
+            run_until(|| true, 20),
+            Some(10)
+        );
+
+        assert_eq!(
+            run_until_idle(5),
+            Some(10)
+        );
+    });
+}
+
+#[test]
+pub fn test_block_time_and_timestamp_control() {
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        set_block_time(6_000);
+
+        run_to_next_block();
+
+        assert_eq!(
+            current_timestamp(),
+            3_000 + 6_000
+        );
+
+        // Jump one hour without changing the block
+        advance_time(3_600_000);
+
+        assert_eq!(
+            current_block(),
+            2
+        );
+
+        assert_eq!(
+            current_timestamp(),
+            9_000 + 3_600_000
+        );
+
+        freeze_time();
+
+        run_for_n_blocks(10, None);
+
+        assert_eq!(
+            current_timestamp(),
+            9_000 + 3_600_000
+        );
+
+        unfreeze_time();
+        set_timestamp(1_000_000_000);
+
+        run_to_next_block();
+
+        assert_eq!(
+            current_timestamp(),
+            1_000_000_000 + 6_000
+        );
+    });
+}
+
+#[test]
+pub fn test_runtime_parameter_overrides() {
+    let authorities = vec![
+        (VAL_1_STASH, Some(UintAuthorityId(VAL_1_STASH_AUTH_ID))),
+    ];
+
+    new_ext_builder(vec![SIGNER])
+        .with_initial_authorities(authorities)
+        .with_sessions()
+        .with_session_duration(100)
+        .with_sessions_per_era(2)
+        .with_bonding_duration(3)
+        .with_existential_deposit(10 * ONE_TOKEN)
+        .with_era_payout(5 * ONE_TOKEN)
+        .build()
+        .execute_with(|| {
+            assert_eq!(session_duration_in_blocks(), 100);
+            assert_eq!(sessions_per_era(), 2);
+            assert_eq!(bonding_duration(), 3);
+            assert_eq!(existential_deposit(), 10 * ONE_TOKEN);
+            assert_eq!(era_payout(), 5 * ONE_TOKEN);
+
+            advance_to_next_era();
+
+            assert_eq!(
+                current_block(),
+                201
+            );
+
+            assert_eq!(
+                current_era_index(),
+                1
+            );
+        });
+
+    // Next tests in the same thread use the default values
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        assert_eq!(session_duration_in_blocks(), 2_400);
+        assert_eq!(sessions_per_era(), 6);
+        assert_eq!(bonding_duration(), 14);
+        assert_eq!(existential_deposit(), ONE_TOKEN);
+        assert_eq!(era_payout(), 100 * ONE_TOKEN);
+    });
+}
+
+#[test]
+pub fn test_nested_rollback() {
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let block = current_block();
+
+        let inner_block = with_rollback(|| {
+            run_to_next_block();
+
+            assert_eq!(transaction_depth(), 1);
+
+            with_rollback(|| {
+                run_for_n_blocks(5, None);
+                assert_eq!(transaction_depth(), 2);
+            });
+
+            assert_eq!(current_block(), block + 1);
+
+            current_block()
+        });
+
+        assert_eq!(inner_block, block + 1);
+        assert_eq!(current_block(), block);
+        assert!(!in_transaction());
+
+        // Changes are kept only if the closure returns Ok
+        let result: Result<(), &str> = with_transaction(|| {
+            run_to_next_block();
+            Err("scenario failed")
+        });
+
+        assert!(result.is_err());
+        assert_eq!(current_block(), block);
+
+        let result: Result<(), &str> = with_transaction(|| {
+            run_to_next_block();
+            Ok(())
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(current_block(), block + 1);
+
+        // Panics roll back the changes, even from transactions left open
+        let result = std::panic::catch_unwind(|| {
+            with_rollback(|| {
+                run_to_next_block();
+                start_transaction();
+                panic!("scenario failed");
+            })
+        });
+
+        assert!(result.is_err());
+        assert_eq!(current_block(), block + 1);
+        assert_eq!(transaction_depth(), 0);
+    });
+}
+
+#[test]
+pub fn test_cached_genesis_matches_new_genesis() {
+    let authorities = vec![
+        (VAL_1_STASH, Some(UintAuthorityId(VAL_1_STASH_AUTH_ID))),
+    ];
+
+    let take_state = |builder: vrtest::ext_builder::ExtBuilder| {
+        builder.build().execute_with(|| {
+            (
+                StateSnapshot::take().len(),
+                balance_from_user(SIGNER),
+                current_era_index(),
+            )
+        })
+    };
+
+    let new_builder = || {
+        new_ext_builder(vec![SIGNER])
+            .with_initial_authorities(authorities.clone())
+            .with_sessions()
+    };
+
+    // The first build stores the genesis in the cache, the second one uses it
+    let first_state = take_state(new_builder());
+    let cached_state = take_state(new_builder());
+    let new_state = take_state(new_builder().without_genesis_cache());
+
+    assert_eq!(first_state, cached_state);
+    assert_eq!(cached_state, new_state);
+}
+
+#[test]
+pub fn test_chain_state_with_remapped_accounts() {
+    use sp_core::{blake2_128, hexdisplay::HexDisplay, twox_128};
+
+    const CHAIN_ACCOUNT: [u8; 32] = [7; 32];
+    const TEST_ACCOUNT: u64 = 77;
+
+    // System::Account uses Blake2_128Concat keys
+    let account_key = |account: &[u8]| {
+        [
+            twox_128(b"System").as_slice(),
+            &twox_128(b"Account"),
+            &blake2_128(account),
+            account,
+        ]
+        .concat()
+    };
+    let to_hex = |bytes: &[u8]| format!("0x{}", HexDisplay::from(&bytes));
+
+    // Account info of an endowed account, as it would be exported from a node
+    let account_info = new_ext_builder(vec![SIGNER, TEST_ACCOUNT])
+        .build()
+        .execute_with(|| {
+            sp_io::storage::get(&account_key(&TEST_ACCOUNT.to_le_bytes()))
+                .expect("account info")
+                .to_vec()
+        });
+
+    // Unique per process, so parallel test runs don't share the file
+    let chain_state_file =
+        std::env::temp_dir().join(format!("vrtest-chain-state-{}.json", std::process::id()));
+    let chain_state_json = serde_json::json!({
+        "genesis": {
+            "raw": {
+                "top": {
+                    to_hex(&account_key(&CHAIN_ACCOUNT)): to_hex(&account_info),
+                    to_hex(b":code"): "0x00",
+                }
+            }
+        }
+    });
+
+    std::fs::write(&chain_state_file, chain_state_json.to_string()).expect("chain state saved");
+
+    let chain_state = ChainState::load(&chain_state_file).expect("chain state loaded");
+
+    std::fs::remove_file(&chain_state_file).expect("chain state removed");
+
+    // `:code` is skipped when the file is loaded (without a pallets filter)
+    assert_eq!(chain_state.len(), 1);
+
+    let chain_state = chain_state
+        .pallets(&["System"])
+        .remap_account(CHAIN_ACCOUNT, TEST_ACCOUNT);
+    assert_eq!(chain_state.len(), 1);
+
+    new_ext_builder(vec![SIGNER])
+        .with_chain_state(chain_state)
+        .build()
+        .execute_with(|| {
+            assert_eq!(balance_from_user(TEST_ACCOUNT), balance_from_user(SIGNER));
+        });
+}
+
+#[test]
+pub fn test_block_hooks() {
+    use std::{cell::RefCell, rc::Rc};
+
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let before_blocks = Rc::new(RefCell::new(vec![]));
+        let after_blocks = Rc::new(RefCell::new(vec![]));
+
+        let blocks = before_blocks.clone();
+        let before_hook = add_before_block_hook(move |block| blocks.borrow_mut().push(block));
+
+        let blocks = after_blocks.clone();
+        add_after_block_hook(move |block, _events| blocks.borrow_mut().push(block));
+
+        run_for_n_blocks(3, None);
+
+        assert_eq!(*before_blocks.borrow(), vec![1, 2, 3]);
+        assert_eq!(*after_blocks.borrow(), vec![1, 2, 3]);
+
+        assert!(remove_block_hook(before_hook));
+        assert!(!remove_block_hook(before_hook));
+
+        // Hooks also run in idle blocks
+        fast_forward_blocks(2);
+
+        assert_eq!(*before_blocks.borrow(), vec![1, 2, 3]);
+        assert_eq!(*after_blocks.borrow(), vec![1, 2, 3, 4, 5]);
+    });
+
+    // Hooks are removed when a new test starts
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let blocks_run = Rc::new(RefCell::new(0));
+        let counter = blocks_run.clone();
+
+        add_after_block_hook(move |_, _| *counter.borrow_mut() += 1);
+
+        run_to_next_block();
+
+        assert_eq!(*blocks_run.borrow(), 1);
+    });
+}
+
+#[test]
+pub fn test_gear_invariants_report() {
+    use vrtest::types::{GearInvariantsReport, LeakedGasNodes};
+
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let report = check_gear_invariants().expect("gear invariants are met");
+
+        assert!(report.is_ok());
+        assert_eq!(report.block, current_block());
+    });
+
+    let report = GearInvariantsReport {
+        block: 10,
+        bank_balance: 100,
+        gas_deposits: 80,
+        value_deposits: 30,
+        unused_value: 0,
+        leaked_gas_nodes: vec![LeakedGasNodes {
+            origin: SIGNER,
+            nodes: 2,
+            gas: 1_000,
+        }],
+    };
+
+    assert!(!report.is_ok());
+
+    let message = report.to_string();
+
+    assert!(message.contains("GearBank balance (100) is lower than its deposits (110)"));
+    assert!(message.contains("Leaked gas nodes of origin 1: 2 nodes with 1000 gas"));
+}
+
+#[test]
+pub fn test_random_seed_and_outputs() {
+    let subject = b"lottery";
+
+    let seeded_output = new_test_ext(vec![SIGNER]).execute_with(|| {
+        // Default randomness: the subject padded with zeros
+        let (output, block) = random(subject);
+        let mut expected = [0u8; 32];
+        expected[..subject.len()].copy_from_slice(subject);
+
+        assert_eq!(output, expected);
+        assert_eq!(block, current_block());
+
+        set_random_seed(Some(42));
+        let (seeded_output, _) = random(subject);
+
+        assert_ne!(seeded_output, expected);
+        assert_eq!(random(subject).0, seeded_output);
+
+        set_random_seed(Some(43));
+        assert_ne!(random(subject).0, seeded_output);
+
+        set_random_output(Some([7; 32]));
+        assert_eq!(random(subject).0, [7; 32]);
+
+        set_random_output_at(current_block() + 1, [9; 32]);
+        assert_eq!(random(subject).0, [7; 32]);
+
+        run_to_next_block();
+        assert_eq!(random(subject).0, [9; 32]);
+
+        run_to_next_block();
+        assert_eq!(random(subject).0, [7; 32]);
+
+        reset_randomness();
+        assert_eq!(random(subject).0, expected);
+
+        set_random_seed(Some(42));
+        seeded_output
+    });
+
+    // Same seed in another test gives the same outputs, the settings are reset between tests
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        assert_eq!(random_seed(), None);
+
+        set_random_seed(Some(42));
+        assert_eq!(random(subject).0, seeded_output);
+    });
+}
+
+#[test]
+pub fn test_custom_balances() {
+    const POOR_USER: u64 = 2;
+    const EMPTY_USER: u64 = 3;
+
+    new_test_ext_with_balances(vec![
+        (SIGNER, 5 * ONE_TOKEN),
+        (POOR_USER, existential_deposit()),
+        (EMPTY_USER, 0),
+    ])
+    .execute_with(|| {
+        assert_eq!(balance_from_user(SIGNER), 5 * ONE_TOKEN);
+        assert_eq!(balance_from_user(POOR_USER), existential_deposit());
+        assert_eq!(balance_from_user(EMPTY_USER), 0);
+    });
+
+    // Authorities that are also endowed accounts are funded once
+    new_ext_builder(vec![SIGNER, VAL_1_STASH])
+        .with_initial_authorities(vec![(VAL_1_STASH, None)])
+        .with_balance(SIGNER, 10 * ONE_TOKEN)
+        .build()
+        .execute_with(|| {
+            assert_eq!(balance_from_user(SIGNER), 10 * ONE_TOKEN);
+            assert_eq!(balance_from_user(VAL_1_STASH), 1_000 * ONE_TOKEN);
+        });
+}
+
+#[test]
+#[should_panic(expected = "is lower than the existential deposit")]
+pub fn test_balance_lower_than_existential_deposit() {
+    new_test_ext_with_balances(vec![(SIGNER, ONE_TOKEN / 2)]);
+}
+
+#[test]
+#[should_panic(expected = "has no balance to bond")]
+pub fn test_authority_without_balance() {
+    new_ext_builder(vec![SIGNER])
+        .with_initial_authorities(vec![(VAL_1_STASH, None)])
+        .with_balance(VAL_1_STASH, 0)
+        .build();
+}
+
+#[test]
+pub fn test_queue_not_processed_reports() {
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        allow_queue_not_processed(true);
+
+        let first_block = current_block();
+        skip_gear_run(2);
+        run_for_n_blocks(3, None);
+
+        // Only the skipped blocks are reported, without a Gear run error
+        let reports = queue_not_processed_reports();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].block, first_block);
+        assert_eq!(reports[1].block, first_block + 1);
+        assert!(reports.iter().all(|report| report.error.is_none()));
+
+        clear_queue_not_processed_reports();
+        assert!(queue_not_processed_reports().is_empty());
+    });
+}
+
+#[test]
+#[should_panic(expected = "Gear queue was not processed")]
+pub fn test_queue_not_processed_fails_by_default() {
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        skip_gear_run(1);
+        run_to_next_block();
+    });
+}
+
+#[test]
+pub fn test_fast_forward_keeps_block_bookkeeping() {
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        allow_queue_not_processed(true);
+
+        // The skipped runs are taken by the first blocks, even if they are idle
+        let first_block = current_block();
+        skip_gear_run(2);
+        fast_forward_blocks(5);
+
+        let reports = queue_not_processed_reports();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].block, first_block);
+        assert_eq!(reports[1].block, first_block + 1);
+
+        // The last block info is the last idle block, with the gas allowance unused
+        let block_info = last_block_info().unwrap();
+        assert_eq!(block_info.block, first_block + 4);
+        assert_eq!(block_info.gas_allowance_left, block_gas_limit());
+        assert_eq!(block_info.dispatches_left, 0);
+
+        // No more runs are skipped
+        run_to_next_block();
+        assert_eq!(queue_not_processed_reports().len(), 2);
+    });
+}
+
+#[test]
+pub fn test_balance_helpers() {
+    const OTHER_USER: u64 = 2;
+    const LOCK_ID: [u8; 8] = *b"testlock";
+
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        set_balance(SIGNER, 100 * ONE_TOKEN);
+        assert_eq!(balance_from_user(SIGNER), 100 * ONE_TOKEN);
+
+        assert_eq!(transfer(SIGNER, OTHER_USER, 10 * ONE_TOKEN), Ok(()));
+        assert_eq!(balance_from_user(OTHER_USER), 10 * ONE_TOKEN);
+
+        // Locked and frozen tokens can't be transferred
+        set_lock(LOCK_ID, SIGNER, 80 * ONE_TOKEN);
+        assert_eq!(lock(LOCK_ID, SIGNER), Some(80 * ONE_TOKEN));
+        assert_eq!(locks(SIGNER), vec![(LOCK_ID, 80 * ONE_TOKEN)]);
+        assert!(transfer(SIGNER, OTHER_USER, 20 * ONE_TOKEN).is_err());
+
+        remove_lock(LOCK_ID, SIGNER);
+        assert_eq!(lock(LOCK_ID, SIGNER), None);
+
+        assert_eq!(freeze(LOCK_ID, SIGNER, 80 * ONE_TOKEN), Ok(()));
+        assert_eq!(freezes(SIGNER), vec![(LOCK_ID, 80 * ONE_TOKEN)]);
+        assert!(transfer(SIGNER, OTHER_USER, 20 * ONE_TOKEN).is_err());
+
+        assert_eq!(thaw(LOCK_ID, SIGNER), Ok(()));
+        assert!(freezes(SIGNER).is_empty());
+
+        assert_eq!(reserve(SIGNER, 30 * ONE_TOKEN), Ok(()));
+        assert_eq!(balance_from_user(SIGNER), 60 * ONE_TOKEN);
+        assert_eq!(reserved_balance(SIGNER), 30 * ONE_TOKEN);
+
+        assert_eq!(unreserve(SIGNER, 10 * ONE_TOKEN), 0);
+        assert_eq!(reserved_balance(SIGNER), 20 * ONE_TOKEN);
+
+        // The free balance is slashed first
+        assert_eq!(slash(SIGNER, 65 * ONE_TOKEN), 65 * ONE_TOKEN);
+        assert_eq!(balance_from_user(SIGNER), 5 * ONE_TOKEN);
+        assert_eq!(reserved_balance(SIGNER), 20 * ONE_TOKEN);
+
+        // Then the reserved balance
+        assert_eq!(slash(SIGNER, 15 * ONE_TOKEN), 15 * ONE_TOKEN);
+        assert_eq!(
+            balance_from_user(SIGNER) + reserved_balance(SIGNER),
+            10 * ONE_TOKEN
+        );
+        assert!(reserved_balance(SIGNER) <= 10 * ONE_TOKEN);
+    });
+}
+
+#[test]
+pub fn test_account_names_only_in_account_fields() {
+    const RECEIVER: u64 = 2;
+    // Account with the same digits as the transferred amount
+    const AMOUNT_ACCOUNT: u64 = ONE_TOKEN as u64;
+
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        TestAccount::named(RECEIVER, "receiver");
+        TestAccount::named(AMOUNT_ACCOUNT, "amount_account");
+
+        assert_eq!(transfer(SIGNER, RECEIVER, ONE_TOKEN), Ok(()));
+
+        let events = events_dump();
+        assert!(events.contains("to: receiver"), "{events}");
+        assert!(events.contains(&format!("amount: {ONE_TOKEN}")), "{events}");
+        assert!(!events.contains("amount_account"), "{events}");
+    });
+}
+
+#[test]
+pub fn test_actor_id32_addresses() {
+    const ALICE_HEX: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+    const ALICE_VARA: &str = "kGkLEU3e3XXkJp2WK4eNpVmSab5xUNL9QtmLPh8QfCL2EgotW";
+    const ALICE_SUBSTRATE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    let alice: ActorId32 = ALICE_HEX.parse().unwrap();
+
+    assert_eq!(alice.to_string(), ALICE_VARA);
+    assert_eq!(alice.to_hex(), ALICE_HEX);
+    assert_eq!(alice.to_ss58_with_prefix(42), ALICE_SUBSTRATE);
+    assert_eq!(format!("{alice:?}"), format!("ActorId32({ALICE_VARA})"));
+
+    // SS58 addresses are parsed with any prefix
+    assert_eq!(ALICE_VARA.parse::<ActorId32>().unwrap(), alice);
+    assert_eq!(ALICE_SUBSTRATE.parse::<ActorId32>().unwrap(), alice);
+
+    let error: Box<dyn std::error::Error> = "0x1234".parse::<ActorId32>().unwrap_err().into();
+    assert_eq!(
+        error.to_string(),
+        "Invalid address length: 2 bytes (32 expected)"
+    );
+    assert!("kGkLEU3e3XXkJp2WK4eNpVmSab5xUNL9QtmLPh8QfCL2Egot1".parse::<ActorId32>().is_err());
+
+    let json = serde_json::to_string(&alice).unwrap();
+    assert_eq!(json, format!("\"{ALICE_VARA}\""));
+    assert_eq!(serde_json::from_str::<ActorId32>(&json).unwrap(), alice);
+}
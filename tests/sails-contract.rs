@@ -1,3 +1,6 @@
+// The tests use `u64` accounts, the 32-byte accounts mode is tested in `account_id32.rs`
+#![cfg(not(feature = "account-id32"))]
+
 use common::{ActorId, Origin};
 use vrtest::{
     contract::{Contract, builders::UploadWasmT},
@@ -190,3 +193,283 @@ pub fn get_set_counter() {
 }
 
 
+
+#[test]
+pub fn run_until_contract_is_idle() {
+    init_logger();
+
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let contract = Contract::upload_sails_contract::<()>()
+            .signer(SIGNER)
+            .salt("contract")
+            .app_constructor_name("New")
+            .wasm(WASM_BINARY)
+            .upload();
+
+        let result = contract.new_command()
+            .signer(SIGNER)
+            .service_name("ContractService")
+            .method_name("Increment")
+            .send();
+
+        assert!(result.is_ok());
+
+        // The message is in the queue
+        assert!(!gear_idle());
+
+        let block_sent = current_block();
+        let idle_block = run_until_idle(5);
+
+        assert_eq!(idle_block, Some(block_sent + 1));
+        assert!(message_queue_empty());
+
+        // Condition never met
+        assert_eq!(run_until(|| false, 3), None);
+        assert_eq!(current_block(), block_sent + 4);
+    });
+}
+
+fn counter_value(contract: &Contract) -> u64 {
+    let result = contract.new_command()
+        .signer(SIGNER)
+        .service_name("ContractService")
+        .method_name("CounterValue")
+        .transform_to_query()
+        .send_recv::<u64>();
+
+    let Ok(ContractResponse::Response(response)) = result else {
+        panic!("Panic reading state!");
+    };
+
+    response
+}
+
+fn increment_counter(contract: &Contract) {
+    let result = contract.new_command()
+        .signer(SIGNER)
+        .service_name("ContractService")
+        .method_name("Increment")
+        .send_and_run_one_block();
+
+    assert!(result.is_ok());
+}
+
+#[test]
+pub fn snapshot_shared_between_scenarios() {
+    init_logger();
+
+    // Expensive setup, made once
+    let (contract, snapshot, snapshot_block) = new_test_ext(vec![SIGNER]).execute_with(|| {
+        let contract = Contract::upload_sails_contract::<()>()
+            .signer(SIGNER)
+            .salt("contract")
+            .app_constructor_name("New")
+            .wasm(WASM_BINARY)
+            .upload();
+
+        increment_counter(&contract);
+
+        let snapshot = StateSnapshot::take();
+        let snapshot_block = current_block();
+
+        increment_counter(&contract);
+        assert_eq!(counter_value(&contract), 2);
+
+        // Back to the snapshot state
+        snapshot.restore();
+        assert_eq!(counter_value(&contract), 1);
+
+        (contract, snapshot, snapshot_block)
+    });
+
+    // Independent scenarios from the same setup
+    snapshot.execute_with(|| {
+        increment_counter(&contract);
+        increment_counter(&contract);
+        assert_eq!(counter_value(&contract), 3);
+    });
+
+    snapshot.execute_with(|| {
+        assert_eq!(counter_value(&contract), 1);
+        assert_eq!(current_block(), snapshot_block);
+    });
+}
+
+#[test]
+pub fn code_cache_reused_between_tests() {
+    use gear_core::ids::{prelude::CodeIdExt, CodeId};
+
+    init_logger();
+
+    let code_id = CodeId::generate(WASM_BINARY);
+
+    clear_code_cache();
+
+    for test in 0..2 {
+        new_ext_builder(vec![SIGNER])
+            .with_code_cache()
+            .build()
+            .execute_with(|| {
+                let contract = Contract::upload_sails_contract::<()>()
+                    .signer(SIGNER)
+                    .salt("contract")
+                    .app_constructor_name("New")
+                    .wasm(WASM_BINARY)
+                    .upload();
+
+                // Saved by the first upload, used in the second test
+                assert!(code_cached(code_id));
+                assert_eq!(code_cache_hits(code_id), test);
+
+                // The cached code is not validated and instrumented again
+                assert_eq!(events_dump().contains("CodeChanged"), test == 0);
+
+                increment_counter(&contract);
+                assert_eq!(counter_value(&contract), 1);
+            });
+    }
+}
+
+#[test]
+pub fn state_saved_to_file_and_loaded() {
+    init_logger();
+
+    // Unique per process, so parallel test runs don't share the file
+    let state_file = std::env::temp_dir().join(format!(
+        "vrtest-sails-contract-state-{}.bin",
+        std::process::id()
+    ));
+
+    let contract = new_test_ext(vec![SIGNER]).execute_with(|| {
+        let contract = Contract::upload_sails_contract::<()>()
+            .signer(SIGNER)
+            .salt("contract")
+            .app_constructor_name("New")
+            .wasm(WASM_BINARY)
+            .upload();
+
+        increment_counter(&contract);
+
+        StateSnapshot::take()
+            .save(&state_file)
+            .expect("state file saved");
+
+        contract
+    });
+
+    let snapshot = StateSnapshot::load(&state_file).expect("state file loaded");
+
+    snapshot.execute_with(|| {
+        assert_eq!(counter_value(&contract), 1);
+    });
+
+    std::fs::remove_file(&state_file).expect("state file removed");
+}
+
+#[test]
+pub fn contract_balance_checked_in_every_block() {
+    use pallet_gear::Event as GearEvent;
+    use std::{cell::RefCell, rc::Rc};
+    use vrtest::types::RuntimeEvent;
+
+    init_logger();
+
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let contract = Contract::upload_sails_contract::<()>()
+            .signer(SIGNER)
+            .salt("contract")
+            .app_constructor_name("New")
+            .wasm(WASM_BINARY)
+            .upload();
+
+        let dispatched_blocks = Rc::new(RefCell::new(vec![]));
+
+        let checked_contract = contract.clone();
+        let blocks = dispatched_blocks.clone();
+        add_after_block_hook(move |block, events| {
+            assert!(
+                checked_contract.free_balance() >= existential_deposit(),
+                "Contract balance below the existential deposit in block {block}"
+            );
+
+            let dispatched = events
+                .iter()
+                .any(|event| matches!(event, RuntimeEvent::Gear(GearEvent::MessagesDispatched { .. })));
+
+            if dispatched {
+                blocks.borrow_mut().push(block);
+            }
+        });
+
+        let result = contract.new_command()
+            .signer(SIGNER)
+            .with_value(ONE_TOKEN)
+            .service_name("ContractService")
+            .method_name("SendValue")
+            .send_recv::<String>();
+
+        assert!(result.is_ok());
+
+        run_for_n_blocks(5, None);
+
+        // The hook received the events of the blocks where the messages were dispatched
+        assert!(!dispatched_blocks.borrow().is_empty());
+    });
+}
+
+#[test]
+pub fn gear_invariants_checked_after_each_block() {
+    init_logger();
+
+    new_ext_builder(vec![SIGNER])
+        .with_gear_invariant_checks()
+        .build()
+        .execute_with(|| {
+            let contract = Contract::upload_sails_contract::<()>()
+                .signer(SIGNER)
+                .salt("contract")
+                .app_constructor_name("New")
+                .wasm(WASM_BINARY)
+                .upload();
+
+            let result = contract.new_command()
+                .signer(SIGNER)
+                .with_value(ONE_TOKEN)
+                .service_name("ContractService")
+                .method_name("SendValue")
+                .send_recv::<String>();
+
+            assert!(result.is_ok());
+
+            run_until_idle(10).expect("contract is idle");
+
+            let report = check_gear_invariants().expect("gear invariants are met");
+
+            assert!(report.leaked_gas_nodes.is_empty());
+            assert!(report.bank_balance >= report.required_bank_balance());
+        });
+}
+
+#[test]
+pub fn contract_account_funded_before_upload() {
+    let contract = Contract::predict(WASM_BINARY, "contract");
+
+    new_ext_builder(vec![SIGNER])
+        .with_balance(contract.account(), 5 * ONE_TOKEN)
+        .build()
+        .execute_with(|| {
+            assert_eq!(contract.free_balance(), 5 * ONE_TOKEN);
+
+            let uploaded_contract = Contract::upload_sails_contract()
+                .signer(SIGNER)
+                .salt("contract")
+                .app_constructor_name("New")
+                .init_payload(())
+                .wasm(WASM_BINARY)
+                .upload();
+
+            assert_eq!(uploaded_contract.address(), contract.address());
+            assert_eq!(uploaded_contract.account(), contract.account());
+            assert!(uploaded_contract.free_balance() >= 5 * ONE_TOKEN);
+        });
+}
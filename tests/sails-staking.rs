@@ -1,3 +1,6 @@
+// The tests use `u64` accounts, the 32-byte accounts mode is tested in `account_id32.rs`
+#![cfg(not(feature = "account-id32"))]
+
 use vrtest::{
     contract::{builders::UploadWasmT, Contract}, runtime::*, types::{
         builtin_staking::RewardAccount,
@@ -643,3 +646,79 @@ fn payout_stakers_works() { // rewards
         );
     });
 }
+
+#[test]
+fn balance_tracker_works_with_commands() {
+    init_logger();
+
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let contract = Contract::upload_sails_contract::<()>()
+            .signer(SIGNER)
+            .salt("contract")
+            .app_constructor_name("New")
+            .wasm(WASM_BINARY)
+            .upload();
+
+        let tracker = BalanceTracker::new([SIGNER]).with_contract(&contract);
+
+        let bond_gas_fees = contract.new_calculate_gas()
+            .signer(SIGNER)
+            .service_name("ContractService")
+            .method_name("Bond")
+            .add_arg((
+                100 * ONE_TOKEN,
+                RewardAccount::Program
+            ))
+            .with_value(100 * ONE_TOKEN)
+            .calculate_gas();
+
+        // `send_check_result` resets the events before sending the message
+        let result = contract.new_command()
+            .signer(SIGNER)
+            .service_name("ContractService")
+            .method_name("Bond")
+            .add_arg(100 * ONE_TOKEN)
+            .add_arg(RewardAccount::Program)
+            .with_value(100 * ONE_TOKEN)
+            .send_check_result(5);
+
+        assert!(result.is_ok());
+
+        let bond_extra_gas_fees = contract.new_calculate_gas()
+            .signer(SIGNER)
+            .service_name("ContractService")
+            .method_name("Bond")
+            .add_arg((
+                50 * ONE_TOKEN,
+                RewardAccount::Program
+            ))
+            .with_value(50 * ONE_TOKEN)
+            .calculate_gas();
+
+        // `send_recv` resets the events again, the events of the first bond must not be lost
+        let result = contract.new_command()
+            .signer(SIGNER)
+            .service_name("ContractService")
+            .method_name("Bond")
+            .add_arg(50 * ONE_TOKEN)
+            .add_arg(RewardAccount::Program)
+            .with_value(50 * ONE_TOKEN)
+            .send_recv::<()>();
+
+        assert!(result.is_ok());
+
+        let gas_paid = (bond_gas_fees.burned + bond_extra_gas_fees.burned) as i128;
+        let signer = tracker.delta(SIGNER);
+
+        assert_eq!(signer.value_sent, 150 * ONE_TOKEN);
+        assert_eq!(signer.gas_paid, gas_paid);
+        tracker.assert_free_delta(SIGNER, -(150 * ONE_TOKEN as i128) - gas_paid);
+
+        let contract_delta = tracker.delta(contract.account());
+
+        assert_eq!(contract_delta.value_received, 150 * ONE_TOKEN);
+        assert_eq!(contract_delta.frozen, 150 * ONE_TOKEN as i128);
+
+        tracker.assert_explained();
+    });
+}
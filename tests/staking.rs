@@ -1,3 +1,6 @@
+// The tests use `u64` accounts, the 32-byte accounts mode is tested in `account_id32.rs`
+#![cfg(not(feature = "account-id32"))]
+
 use vrtest::{
     contract::{Contract, builders::UploadWasmT}, 
     runtime::*, 
@@ -8,10 +11,13 @@ use vrtest::{
         CONTRACT_EXISTENCIAL_DEPOSIT, 
         ENDOWMENT, 
         ONE_TOKEN, 
-        SESSION_DURATION_IN_BLOCKS
+        SESSION_DURATION_IN_BLOCKS,
+        STAKING_LOCK_ID,
+        VaraRewardsConfig
     } 
 };
 use common::Origin;
+use sp_runtime::{testing::UintAuthorityId, Perbill};
 // use gprimitives::ActorId;
 
 // use gbuiltin_staking::{Request, RewardAccount};
@@ -88,6 +94,7 @@ fn bonding_works() {
         assert_eq!(contract.free_balance(), 100 * ONE_TOKEN + CONTRACT_EXISTENCIAL_DEPOSIT);
         // and all of it is frozen as bonded or locked
         assert_eq!(contract.frozen_balance(), 100 * ONE_TOKEN);
+        assert_eq!(lock(STAKING_LOCK_ID, contract.account()), Some(100 * ONE_TOKEN));
 
         // Asserting the expected events are present
         assert_staking_events(&contract, 100 * ONE_TOKEN, StakingEventType::Bonded);
@@ -659,75 +666,286 @@ fn payout_stakers_works() { // rewards
     });
 }
 
-// #[test]
-// fn gas_allowance_respected() {
-//     init_logger();
-
-//     new_test_ext(vec![SIGNER]).execute_with(|| {
-//         let contract = upload_wasm(
-//             demo_staking_broker::WASM_BINARY, 
-//             SIGNER, 
-//             b"contract", 
-//             None
-//         );
-
-//         // Asserting success with ample remaining gas in the block
-
-//         let result = send_message_and_run_one_block(
-//             SIGNER, 
-//             &contract, 
-//             Request::Bond { 
-//                 value: 100 * ONE_TOKEN, 
-//                 payee: RewardAccount::Program
-//             }, 
-//             100 * ONE_TOKEN, 
-//             Some(1_000_000_000_000)
-//         );
-
-//         assert!(result.is_ok());
-
-//         assert_staking_events(&contract, 100 * ONE_TOKEN, StakingEventType::Bonded);
-
-//         // Estimate the gas cost for sending a message to `staking-broker` contract (without any
-//         // outgoing messages).
-//         // TODO: find a way to use `calculate_gas_info` here for more precise estimation.
-//         // To block the contract from sending any messages, we provide an illegal payload. But
-//         // we have to use the "actual" `calculate_gas_info` (since `cfg(test)` is not propagated
-//         // to the dependencies), and that one doesn't allow calculating gas for failed dispatches.
-//         let gas_to_engage_staking_broker = 880_000_000_u64; // Heuristic value
-
-//         let gas_cost = temp_gas__cost();
-
-//         reset_system_events();
-
-//         // With insufficient gas allowance, the message should not be processed
-
-//         let result = send_message_and_run_one_block(
-//             SIGNER, 
-//             &contract, 
-//             Request::Bond { 
-//                 value: 100 * ONE_TOKEN, 
-//                 payee: RewardAccount::Program
-//             }, 
-//             100 * ONE_TOKEN, 
-//             None
-//         );
-
-//         assert!(result.is_ok());
-
-//         run_for_n_blocks(1, Some(gas_to_engage_staking_broker + gas_cost));
-
-//         // No staking events have taken place
-//         assert_no_staking_events();
-
-//         // The dispatch is still in the queue
-//         assert!(!message_queue_empty());
-
-//         // Increasing gas allowance will push the message through
-//         run_to_next_block();
-//         assert_staking_events(&contract, 100 * ONE_TOKEN, StakingEventType::Bonded);
-
-//         // Message queue is now empty
-//         assert!(message_queue_empty());
-//     });
-// }
+#[test]
+fn gas_allowance_respected() {
+    init_logger();
+
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let contract = Contract::upload_contract()
+            .signer(SIGNER)
+            .salt("contract")
+            .wasm(demo_staking_broker::WASM_BINARY)
+            .upload();
+
+        // Asserting success with ample remaining gas in the block
+        let result = contract.new_command()
+            .signer(SIGNER)
+            .with_value(100 * ONE_TOKEN)
+            .add_arg(Request::Bond { 
+                value: 100 * ONE_TOKEN, 
+                payee: RewardAccount::Program 
+            })
+            .no_sails_command()
+            .send_and_run_one_block();
+
+        assert!(result.is_ok());
+
+        assert_staking_events(&contract, 100 * ONE_TOKEN, StakingEventType::Bonded);
+
+        reset_system_events();
+
+        // With insufficient gas allowance, the message should not be processed
+        set_gas_allowance(Some(1_000_000));
+
+        let result = contract.new_command()
+            .signer(SIGNER)
+            .with_value(100 * ONE_TOKEN)
+            .add_arg(Request::Bond { 
+                value: 100 * ONE_TOKEN, 
+                payee: RewardAccount::Program 
+            })
+            .no_sails_command()
+            .send_and_run_one_block();
+
+        assert!(result.is_ok());
+
+        // No staking events have taken place
+        assert_no_staking_events();
+
+        // The dispatch is still in the queue
+        assert!(!message_queue_empty());
+        assert_eq!(last_block_info().unwrap().dispatches_left, 1);
+
+        // Increasing gas allowance will push the message through
+        set_gas_allowance(None);
+        run_to_next_block();
+
+        assert_staking_events(&contract, 100 * ONE_TOKEN, StakingEventType::Bonded);
+
+        // Message queue is now empty
+        assert!(message_queue_empty());
+        assert_eq!(last_block_info().unwrap().dispatches_left, 0);
+    });
+}
+
+#[test]
+fn bonding_tracked_by_balance_tracker() {
+    init_logger();
+
+    new_test_ext(vec![SIGNER]).execute_with(|| {
+        let contract = Contract::upload_contract()
+            .signer(SIGNER)
+            .salt("contract")
+            .wasm(demo_staking_broker::WASM_BINARY)
+            .upload();
+
+        let tracker = BalanceTracker::new([SIGNER]).with_contract(&contract);
+
+        let gas_fees = contract.new_calculate_gas()
+            .signer(SIGNER)
+            .with_value(100 * ONE_TOKEN)
+            .add_arg(Request::Bond { 
+                value: 100 * ONE_TOKEN,
+                payee: RewardAccount::Program 
+            })
+            .no_sails_command()
+            .calculate_gas();
+
+        let result = contract.new_command()
+            .signer(SIGNER)
+            .with_value(100 * ONE_TOKEN)
+            .add_arg(Request::Bond { 
+                value: 100 * ONE_TOKEN,
+                payee: RewardAccount::Program 
+            })
+            .no_sails_command()
+            .send();
+
+        assert!(result.is_ok());
+
+        run_to_next_block();
+
+        let signer = tracker.delta(SIGNER);
+
+        assert_eq!(signer.value_sent, 100 * ONE_TOKEN);
+        assert_eq!(signer.gas_paid, gas_fees.burned as i128);
+        tracker.assert_free_delta(SIGNER, -(100 * ONE_TOKEN as i128) - gas_fees.burned as i128);
+
+        let contract_delta = tracker.delta(contract.account());
+
+        assert_eq!(contract_delta.value_received, 100 * ONE_TOKEN);
+        assert_eq!(contract_delta.frozen, 100 * ONE_TOKEN as i128);
+
+        tracker.assert_explained();
+    });
+}
+
+#[test]
+fn rotating_authors_and_auto_payout_work() {
+    init_logger();
+
+    let validators = [VAL_1_STASH, VAL_2_STASH, VAL_3_STASH];
+    let authorities = vec![
+        (VAL_1_STASH, Some(UintAuthorityId(VAL_1_STASH_AUTH_ID))),
+        (VAL_2_STASH, Some(UintAuthorityId(VAL_2_STASH_AUTH_ID))),
+        (VAL_3_STASH, Some(UintAuthorityId(VAL_3_STASH_AUTH_ID))),
+    ];
+
+    new_ext_builder(vec![SIGNER])
+        .with_initial_authorities(authorities)
+        .with_sessions()
+        .with_author_rotation()
+        .with_auto_payout()
+        .build()
+        .execute_with(|| {
+            let era = current_era_index();
+            let initial_balances: Vec<u128> = validators
+                .iter()
+                .map(|validator| balance_from_user(*validator))
+                .collect();
+
+            advance_to_next_era();
+
+            // All the validators authored blocks of the era
+            let points = era_reward_points(era);
+            assert_eq!(points.len(), validators.len());
+            assert!(points.iter().all(|(_, points)| *points > 0));
+
+            // and the era reward was paid to them without `payout_stakers` calls, split by their
+            // points (no commission and no nominators)
+            let era_reward = era_validator_reward(era).expect("era reward");
+            let total_points: u32 = points.iter().map(|(_, points)| points).sum();
+            let mut total_paid = 0;
+
+            for (validator, initial_balance) in validators.iter().zip(initial_balances) {
+                let paid = balance_from_user(*validator) - initial_balance;
+                let (_, validator_points) = points
+                    .iter()
+                    .find(|(account, _)| account == validator)
+                    .expect("validator points");
+
+                assert_eq!(
+                    paid,
+                    Perbill::from_rational(*validator_points, total_points) * era_reward
+                );
+                total_paid += paid;
+            }
+
+            // The whole era reward is paid, except the rounding of the shares (Perbill)
+            let max_rounding = validators.len() as u128 * (era_reward / 1_000_000_000 + 1);
+            assert!(total_paid <= era_reward);
+            assert!(era_reward - total_paid <= max_rounding);
+        });
+}
+
+#[test]
+fn snapshot_keeps_author_rotation_and_auto_payout() {
+    init_logger();
+
+    let validators = [VAL_1_STASH, VAL_2_STASH, VAL_3_STASH];
+    let authorities = vec![
+        (VAL_1_STASH, Some(UintAuthorityId(VAL_1_STASH_AUTH_ID))),
+        (VAL_2_STASH, Some(UintAuthorityId(VAL_2_STASH_AUTH_ID))),
+        (VAL_3_STASH, Some(UintAuthorityId(VAL_3_STASH_AUTH_ID))),
+    ];
+
+    let snapshot = new_ext_builder(vec![SIGNER])
+        .with_initial_authorities(authorities)
+        .with_sessions()
+        .with_author_rotation()
+        .with_auto_payout()
+        .build()
+        .execute_with(|| {
+            advance_to_next_era();
+
+            StateSnapshot::take()
+        });
+
+    snapshot.execute_with(|| {
+        assert!(author_rotation_enabled());
+        assert!(auto_payout_enabled());
+
+        // The validators author the blocks in turns
+        let mut authors: Vec<u64> = (0..validators.len())
+            .map(|_| {
+                run_to_next_block();
+                block_author().expect("block author")
+            })
+            .collect();
+
+        authors.sort();
+        assert_eq!(authors, validators);
+
+        // and the era is paid without `payout_stakers` calls
+        let era = current_era_index();
+        let initial_balances: Vec<u128> = validators
+            .iter()
+            .map(|validator| balance_from_user(*validator))
+            .collect();
+
+        advance_to_next_era();
+
+        assert_eq!(era_reward_points(era).len(), validators.len());
+        assert!(era_validator_reward(era).is_some());
+
+        for (validator, initial_balance) in validators.iter().zip(initial_balances) {
+            assert!(balance_from_user(*validator) > initial_balance);
+        }
+    });
+}
+
+#[test]
+fn vara_rewards_model_works() {
+    init_logger();
+
+    let config = VaraRewardsConfig {
+        pool_balance: 1_000 * ONE_TOKEN,
+        ..Default::default()
+    };
+
+    new_ext_builder(vec![SIGNER])
+        .with_initial_authorities(vec![(VAL_1_STASH, Some(UintAuthorityId(VAL_1_STASH_AUTH_ID)))])
+        .with_sessions()
+        .with_vara_rewards(config)
+        .build()
+        .execute_with(|| {
+            assert_eq!(rewards_pool_balance(), 1_000 * ONE_TOKEN);
+
+            // The genesis era is shorter, the payout is checked in a full era
+            advance_to_next_era();
+
+            let era = current_era_index();
+            let era_start = current_timestamp();
+
+            advance_to_next_era();
+
+            let era_duration = current_timestamp() - era_start;
+            let issuance = total_issuance();
+
+            assert_eq!(era_total_stake(era), ENDOWMENT);
+
+            // The era reward is given by the inflation instead of the fixed payout
+            let (validators_payout, _) =
+                vara_era_payout(era_total_stake(era), issuance, era_duration);
+
+            assert!(validators_payout > 0);
+            assert_ne!(validators_payout, era_payout());
+            assert_eq!(era_validator_reward(era), Some(validators_payout));
+
+            let validator_balance = balance_from_user(VAL_1_STASH);
+
+            payout_era(era);
+
+            // The validator gets all the reward (no nominators), funded by the rewards pool, so
+            // the total issuance doesn't change
+            assert_eq!(
+                balance_from_user(VAL_1_STASH) - validator_balance,
+                validators_payout
+            );
+            assert_eq!(
+                rewards_pool_balance(),
+                1_000 * ONE_TOKEN - validators_payout
+            );
+            assert_eq!(total_issuance(), issuance);
+        });
+}
@@ -0,0 +1,37 @@
+// The tests use `u64` accounts, the 32-byte accounts mode is tested in `account_id32.rs`
+#![cfg(all(feature = "try-runtime", not(feature = "account-id32")))]
+
+use sp_runtime::testing::UintAuthorityId;
+use vrtest::runtime::*;
+
+const SIGNER: u64 = 1;
+const VAL_1_STASH: u64 = 10;
+const VAL_1_STASH_AUTH_ID: u64 = 11;
+
+#[test]
+pub fn try_state_checked_after_each_block() {
+    let authorities = vec![
+        (VAL_1_STASH, Some(UintAuthorityId(VAL_1_STASH_AUTH_ID))),
+    ];
+
+    new_ext_builder(vec![SIGNER])
+        .with_initial_authorities(authorities)
+        .with_sessions()
+        .with_session_duration(10)
+        .with_try_state_checks()
+        .build()
+        .execute_with(|| {
+            assert!(try_state_checks_enabled());
+
+            // The era rotation runs idle blocks (fast forward), they are checked too
+            let first_block = current_block();
+            advance_to_next_era();
+            assert_eq!(try_state_checked_blocks(), current_block() - first_block);
+
+            run_for_n_blocks(5, None);
+            assert_eq!(try_state_checked_blocks(), current_block() - first_block);
+
+            assert!(try_state(&TRY_STATE_PALLETS).is_ok());
+            assert_try_state();
+        });
+}
@@ -1,3 +1,6 @@
+// The tests use `u64` accounts, the 32-byte accounts mode is tested in `account_id32.rs`
+#![cfg(not(feature = "account-id32"))]
+
 use common::{ActorId, Origin};
 use vrtest::{
     contract::{Contract, builders::UploadWasmT},
@@ -47,4 +50,82 @@ pub fn upload_create_contract_with_code_id() {
 
         assert_eq!(response, format!("Hello {}", ActorId::from(SIGNER.into_origin())));
     });
-}
\ No newline at end of file
+}
+
+#[test]
+pub fn create_contract_from_loaded_code_state() {
+    init_logger();
+
+    // Only the codes (GearProgram storage) are kept
+    let (contract_code_id, codes_state) = new_test_ext(vec![SIGNER]).execute_with(|| {
+        let contract_code_id = Contract::upload_code()
+            .signer(SIGNER)
+            .wasm(WASM_BINARY)
+            .upload();
+
+        (contract_code_id, StateSnapshot::take_pallets(&["GearProgram"]))
+    });
+
+    new_ext_builder(vec![SIGNER])
+        .with_state(codes_state)
+        .build()
+        .execute_with(|| {
+            let contract = Contract::create_contract()
+                .signer(SIGNER)
+                .salt("contract-hello")
+                .app_constructor_name("New")
+                .code_id(contract_code_id)
+                .init_payload(())
+                .create();
+
+            let result = contract.new_command()
+                .signer(SIGNER)
+                .service_name("ContractService")
+                .method_name("Hello")
+                .add_arg(())
+                .send_recv::<String>();
+
+            assert!(matches!(result, Ok(ContractResponse::Response(_))));
+        });
+}
+
+#[test]
+pub fn named_accounts_in_responses_and_events() {
+    let alice = account("alice");
+
+    new_ext_builder(vec![])
+        .with_funded_accounts([&alice])
+        .build()
+        .execute_with(|| {
+            assert_eq!(alice.free_balance(), 1_000 * ONE_TOKEN);
+            assert_eq!(account_label(alice.id()), "alice");
+            assert_eq!(account_label(SIGNER), SIGNER.to_string());
+
+            let contract = Contract::upload_sails_contract()
+                .signer(alice.id())
+                .salt("contract-hello")
+                .app_constructor_name("New")
+                .init_payload(())
+                .wasm(WASM_BINARY)
+                .upload();
+
+            let result = contract.new_command()
+                .signer(alice.id())
+                .service_name("ContractService")
+                .method_name("Hello")
+                .add_arg(())
+                .send_recv::<String>();
+
+            let Ok(ContractResponse::Response(response)) = result else {
+                panic!("Incorrect response");
+            };
+
+            assert_eq!(response, format!("Hello {}", alice.actor_id()));
+
+            // Gas is paid by alice, the transfers to the gear bank show her name
+            let events = events_dump();
+
+            assert!(events.contains("from: alice"), "{events}");
+            assert!(!events.contains(&alice.id().to_string()));
+        });
+}